@@ -0,0 +1,63 @@
+//! Admin routes, for operators of the app rather than its users.
+//!
+//! Every admin route requires the admin token, set by the `ADMIN_TOKEN` environment
+//! variable, as a bearer token in the `Authorization` header. If no admin token is
+//! set the admin routes always respond `401 Unauthorized`.
+
+use crate::error::AppError;
+use crate::reaper::{Message, UserQueries};
+use crate::AppState;
+use axum::{
+    extract::{FromRequestParts, State},
+    http::{header, request::Parts},
+    Json,
+};
+use subtle::ConstantTimeEq;
+use tokio::sync::oneshot;
+
+/// An extractor that only succeeds if the request carries the admin token.
+
+pub struct Admin;
+
+impl FromRequestParts<AppState> for Admin {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let token = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|auth| auth.to_str().ok())
+            .and_then(|auth| auth.strip_prefix("Bearer "));
+
+        // compared in constant time, so the token can't be guessed from how long
+        // the comparison takes
+        match (token, &state.admin_token) {
+            (Some(token), Some(admin_token))
+                if bool::from(token.as_bytes().ct_eq(admin_token.as_bytes())) =>
+            {
+                Ok(Admin)
+            }
+            _ => Err(AppError::Unauthorized),
+        }
+    }
+}
+
+/// Return every active query the reaper is tracking, grouped by user, with each
+/// query's request id, connection id, kind, chart, and age in seconds.
+
+pub async fn active_queries(
+    _: Admin,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<UserQueries>>, AppError> {
+    // there's no reaper in dummy mode, so nothing could be running
+    if state.connection_pool.is_none() {
+        return Ok(Json(vec![]));
+    }
+
+    let (reply, receiver) = oneshot::channel();
+    state.sender.send(Message::List(reply)).await?;
+    Ok(Json(receiver.await?))
+}
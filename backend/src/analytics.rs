@@ -0,0 +1,329 @@
+//! Search analytics.
+//!
+//! Every chart query is recorded as an anonymised search event: the normalised
+//! search term, the chart, when it finished, how long it took, and its outcome. The
+//! searcher is only kept as an HMAC-SHA256 of the client's address, so that the
+//! same person searching a term for several charts, or under several uuids, counts
+//! once a day.
+//!
+//! The key searchers are hashed with is random, only kept in memory, and replaced
+//! at the start of every day (UTC), so the identifiers in the file can't be traced
+//! back to addresses, or linked across days. A restart also replaces it, so someone
+//! searching both before and after a restart counts twice that day.
+//!
+//! The events of the last week are kept in memory to summarise, and appended to a
+//! file as lines of json so that they survive restarts. The file is compacted to
+//! the last week's events when the backend starts.
+
+use crate::config::AnalyticsConfig;
+use crate::AppState;
+use axum::extract::{Query, State};
+use axum::Json;
+use common::models::{SlowQuery, TrendingPeriod, TrendingRequest, TrendingResponse, TrendingTerm};
+use common::search::{normalise, validate};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::Write;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use time::OffsetDateTime;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+/// The most events kept in memory. The oldest are forgotten first.
+
+const MAX_EVENTS: usize = 200_000;
+
+/// The number of events that can wait to be written to the file before new ones are
+/// dropped from it.
+
+const WRITER_CHANNEL_SIZE: usize = 1000;
+
+/// The number of terms listed as most searched.
+
+const TOP_TERMS: usize = 20;
+
+/// The number of queries listed as slowest.
+
+const SLOWEST_QUERIES: usize = 10;
+
+/// An anonymised record of one chart query.
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SearchEvent {
+    pub term: String,
+    pub chart: String,
+    /// When the query finished, as a unix timestamp.
+    pub timestamp: i64,
+    pub duration_ms: u64,
+    /// Either `ok`, `cancelled`, or `error`.
+    pub outcome: String,
+    /// The client's address, hashed with the key of the day the query finished.
+    /// None for events recorded without one.
+    pub searcher: Option<u64>,
+}
+
+/// The store of recent search events.
+
+pub struct Analytics {
+    events: Mutex<VecDeque<SearchEvent>>,
+    key: Mutex<SearcherKey>,
+    writer: Option<mpsc::Sender<SearchEvent>>,
+}
+
+impl Analytics {
+    /// Loads the last week's events from the analytics file, if one is configured,
+    /// rewrites the file with only those, and starts the task that appends new
+    /// events to it. Problems with the file are logged, and leave the analytics
+    /// empty or unsaved rather than stopping the backend.
+
+    pub fn load(config: &AnalyticsConfig) -> Arc<Analytics> {
+        let mut events = VecDeque::new();
+        let writer = config.save.then_some(&config.path).map(|path| {
+            match compact(path) {
+                Ok(loaded) => events = loaded,
+                Err(error) => tracing::warn!("couldn't load search analytics: {error}"),
+            }
+            let (sender, receiver) = mpsc::channel(WRITER_CHANNEL_SIZE);
+            tokio::spawn(append(path.clone(), receiver));
+            sender
+        });
+
+        Arc::new(Analytics {
+            events: Mutex::new(events),
+            key: Mutex::new(SearcherKey::new(0)),
+            writer,
+        })
+    }
+
+    /// Records a search by the client at the given address for the chart at the
+    /// given path, if it's a chart query and the search term is valid.
+
+    pub fn record(&self, path: &str, search: &str, ip: IpAddr, duration_ms: u64, outcome: &str) {
+        let Some(chart) = chart_of(path) else {
+            return;
+        };
+        let term = normalise(search);
+        if validate(&term).is_err() {
+            return;
+        }
+
+        let timestamp = OffsetDateTime::now_utc().unix_timestamp();
+        let event = SearchEvent {
+            term,
+            chart,
+            timestamp,
+            duration_ms,
+            outcome: outcome.to_owned(),
+            searcher: Some(self.searcher(ip, timestamp)),
+        };
+        if let Some(writer) = &self.writer {
+            let _ = writer.try_send(event.clone());
+        }
+
+        let mut events = self.events.lock().unwrap();
+        let cutoff = event.timestamp - TrendingPeriod::Week.seconds();
+        while events
+            .front()
+            .is_some_and(|oldest| oldest.timestamp < cutoff || events.len() >= MAX_EVENTS)
+        {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+
+    /// Returns the identifier of the client at the given address on the day of the
+    /// timestamp, replacing the key if the day has changed.
+
+    fn searcher(&self, ip: IpAddr, timestamp: i64) -> u64 {
+        let day = timestamp.div_euclid(TrendingPeriod::Day.seconds());
+        let mut key = self.key.lock().unwrap();
+        if key.day != day {
+            *key = SearcherKey::new(day);
+        }
+        let mut mac = Hmac::<Sha256>::new_from_slice(&key.key).expect("any key length is valid");
+        mac.update(ip.to_canonical().to_string().as_bytes());
+        let hash = mac.finalize().into_bytes();
+        u64::from_be_bytes(hash[..8].try_into().unwrap())
+    }
+
+    /// Returns the most searched terms and the slowest queries over the period.
+    ///
+    /// Each client address counts once per term a day. Cancelled queries aren't counted as slow,
+    /// and each chart of a term is only listed once, since identical concurrent
+    /// requests share a query.
+
+    pub fn trending(&self, period: TrendingPeriod) -> TrendingResponse {
+        let cutoff = OffsetDateTime::now_utc().unix_timestamp() - period.seconds();
+        let events = self.events.lock().unwrap();
+        let recent = events.iter().filter(|event| event.timestamp >= cutoff);
+
+        let mut searchers: HashMap<&str, HashSet<u64>> = HashMap::new();
+        let mut anonymous: HashMap<&str, usize> = HashMap::new();
+        let mut slowest: HashMap<(&str, &str), &SearchEvent> = HashMap::new();
+        for event in recent {
+            match event.searcher {
+                Some(searcher) => {
+                    searchers.entry(&event.term).or_default().insert(searcher);
+                }
+                None => *anonymous.entry(&event.term).or_default() += 1,
+            }
+            if event.outcome != "cancelled" {
+                let slow = slowest.entry((&event.term, &event.chart)).or_insert(event);
+                if event.duration_ms > slow.duration_ms {
+                    *slow = event;
+                }
+            }
+        }
+
+        let mut terms = searchers
+            .keys()
+            .chain(anonymous.keys())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .map(|term| TrendingTerm {
+                term: term.to_string(),
+                searches: searchers.get(term).map_or(0, |searchers| searchers.len())
+                    + anonymous.get(term).copied().unwrap_or_default(),
+            })
+            .collect::<Vec<_>>();
+        terms.sort_by(|a, b| {
+            b.searches
+                .cmp(&a.searches)
+                .then_with(|| a.term.cmp(&b.term))
+        });
+        terms.truncate(TOP_TERMS);
+
+        let mut slowest = slowest
+            .into_values()
+            .map(|event| SlowQuery {
+                term: event.term.clone(),
+                chart: event.chart.clone(),
+                duration_ms: event.duration_ms,
+                outcome: event.outcome.clone(),
+                timestamp: event.timestamp,
+            })
+            .collect::<Vec<_>>();
+        slowest.sort_by_key(|slow| Reverse(slow.duration_ms));
+        slowest.truncate(SLOWEST_QUERIES);
+
+        TrendingResponse {
+            period,
+            terms,
+            slowest,
+        }
+    }
+}
+
+/// Return the most searched terms and the slowest chart queries over the last day
+/// or week.
+
+pub async fn trending(
+    State(state): State<AppState>,
+    Query(params): Query<TrendingRequest>,
+) -> Json<TrendingResponse> {
+    Json(state.analytics.trending(params.period))
+}
+
+/// Returns the chart queried by a request path, e.g. `breakdown/party` for
+/// `/api/v1/breakdown/party` or `/api/stream/breakdown/party`, or None if the path
+/// isn't a chart query.
+
+fn chart_of(path: &str) -> Option<String> {
+    let route = path
+        .strip_prefix("/api/v1/")
+        .or_else(|| path.strip_prefix("/api/stream/"))
+        .or_else(|| path.strip_prefix("/api/"))?;
+    let mut segments = route.split('/');
+    match (segments.next()?, segments.next()) {
+        ("population", None) => Some("population".to_owned()),
+        (chart @ ("breakdown" | "dispersion"), Some(breakdown_type)) => {
+            Some(format!("{chart}/{breakdown_type}"))
+        }
+        _ => None,
+    }
+}
+
+/// The key searchers are hashed with on a day, counted in days since the epoch.
+
+struct SearcherKey {
+    day: i64,
+    key: Vec<u8>,
+}
+
+impl SearcherKey {
+    /// Returns a new random key for the day.
+
+    fn new(day: i64) -> SearcherKey {
+        let key = [Uuid::new_v4(), Uuid::new_v4()]
+            .iter()
+            .flat_map(|uuid| uuid.into_bytes())
+            .collect();
+        SearcherKey { day, key }
+    }
+}
+
+/// Reads the events in the analytics file, if it exists, and rewrites it with only
+/// the last week's events, returning them in order.
+
+fn compact(path: &Path) -> Result<VecDeque<SearchEvent>, String> {
+    if !path.exists() {
+        return Ok(VecDeque::new());
+    }
+    let text = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+    let cutoff = OffsetDateTime::now_utc().unix_timestamp() - TrendingPeriod::Week.seconds();
+    let mut events = text
+        .lines()
+        .filter_map(|line| serde_json::from_str::<SearchEvent>(line).ok())
+        .filter(|event| event.timestamp >= cutoff)
+        .collect::<Vec<_>>();
+    events.sort_by_key(|event| event.timestamp);
+    let skip = events.len().saturating_sub(MAX_EVENTS);
+    let events = events.into_iter().skip(skip).collect::<VecDeque<_>>();
+
+    let mut file = std::fs::File::create(path).map_err(|error| error.to_string())?;
+    for event in &events {
+        let line = serde_json::to_string(event).map_err(|error| error.to_string())?;
+        writeln!(file, "{line}").map_err(|error| error.to_string())?;
+    }
+    Ok(events)
+}
+
+/// Appends each event received to the analytics file, as a line of json, creating
+/// the file and its directory if needed.
+
+async fn append(path: PathBuf, mut receiver: mpsc::Receiver<SearchEvent>) {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        if let Err(error) = tokio::fs::create_dir_all(dir).await {
+            tracing::warn!("couldn't create the search analytics directory: {error}");
+            return;
+        }
+    }
+    let mut file = match tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .await
+    {
+        Ok(file) => file,
+        Err(error) => {
+            tracing::warn!("couldn't open the search analytics file: {error}");
+            return;
+        }
+    };
+
+    while let Some(event) = receiver.recv().await {
+        let Ok(mut line) = serde_json::to_string(&event) else {
+            continue;
+        };
+        line.push('\n');
+        if let Err(error) = file.write_all(line.as_bytes()).await {
+            tracing::warn!("couldn't write to the search analytics file: {error}");
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! The versioned public api.
+//!
+//! These routes are read-only GET equivalents of the routes the frontend uses,
+//! taking the search term in the query string, and are described by an OpenAPI
+//! document served at `/api/v1/openapi.json`.
+
+use crate::error::AppError;
+use crate::export::ResponseFormat;
+use crate::handlers::*;
+use crate::AppState;
+use axum::{
+    extract::{ConnectInfo, Path, Query, State},
+    response::Response,
+    Json,
+};
+use common::models::*;
+use std::net::SocketAddr;
+use utoipa::OpenApi;
+
+/// The OpenAPI description of the public api.
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "House Words API",
+        description = "Word usage in parliamentary transcripts, broken down by speaker attributes. \
+            Data routes respond with csv instead of json if requested in the `Accept` header. \
+            Errors respond with a json body holding a machine-readable code and a message."
+    ),
+    paths(speakers_v1, breakdown_v1, dispersion_v1, population_v1, speeches_v1)
+)]
+pub struct ApiDoc;
+
+/// Return the OpenAPI document describing the public api.
+
+pub async fn openapi() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+/// Return all speakers in the database.
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/speakers",
+    responses(
+        (status = 200, description = "All speakers", body = [SpeakerResponse]),
+        (status = 429, description = "Too many requests", body = ErrorResponse),
+        (status = 503, description = "The server is too busy", body = ErrorResponse),
+    )
+)]
+pub async fn speakers_v1(state: State<AppState>) -> Result<Json<Vec<SpeakerResponse>>, AppError> {
+    speakers(state).await
+}
+
+/// Return the number of times the search term was spoken within each category of a
+/// breakdown type, both in total and per 100,000 words spoken.
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/breakdown/{type}",
+    params(
+        ("type" = String, Path, description = "One of `party`, `gender`, `province`, `class`, or `speaker`"),
+        SearchParams,
+    ),
+    responses(
+        (status = 200, description = "One row per category", body = [BreakdownResponse]),
+        (status = 204, description = "The request was cancelled"),
+        (status = 400, description = "The search term or breakdown type is invalid", body = ErrorResponse),
+        (status = 429, description = "Too many requests, or too many queries running", body = ErrorResponse),
+        (status = 503, description = "The server is too busy", body = ErrorResponse),
+        (status = 504, description = "The query took too long", body = ErrorResponse),
+    )
+)]
+pub async fn breakdown_v1(
+    state: State<AppState>,
+    addr: ConnectInfo<SocketAddr>,
+    breakdown_type: Path<String>,
+    format: ResponseFormat,
+    Query(params): Query<SearchParams>,
+) -> Result<Response, AppError> {
+    breakdown(state, addr, breakdown_type, format, Json(params.into())).await
+}
+
+/// Return the usage of the search term by each speaker within each category of a
+/// breakdown type.
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/dispersion/{type}",
+    params(
+        ("type" = String, Path, description = "One of `party`, `gender`, `province`, or `class`"),
+        SearchParams,
+    ),
+    responses(
+        (status = 200, description = "One row per speaker", body = [DispersionResponse]),
+        (status = 204, description = "The request was cancelled"),
+        (status = 400, description = "The search term or breakdown type is invalid", body = ErrorResponse),
+        (status = 429, description = "Too many requests, or too many queries running", body = ErrorResponse),
+        (status = 503, description = "The server is too busy", body = ErrorResponse),
+        (status = 504, description = "The query took too long", body = ErrorResponse),
+    )
+)]
+pub async fn dispersion_v1(
+    state: State<AppState>,
+    addr: ConnectInfo<SocketAddr>,
+    breakdown_type: Path<String>,
+    format: ResponseFormat,
+    Query(params): Query<SearchParams>,
+) -> Result<Response, AppError> {
+    dispersion(state, addr, breakdown_type, format, Json(params.into())).await
+}
+
+/// Return the usage of the search term in each riding, with the riding's
+/// population and area.
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/population",
+    params(SearchParams),
+    responses(
+        (status = 200, description = "One row per riding", body = [PopulationResponse]),
+        (status = 204, description = "The request was cancelled"),
+        (status = 400, description = "The search term or breakdown type is invalid", body = ErrorResponse),
+        (status = 429, description = "Too many requests, or too many queries running", body = ErrorResponse),
+        (status = 503, description = "The server is too busy", body = ErrorResponse),
+        (status = 504, description = "The query took too long", body = ErrorResponse),
+    )
+)]
+pub async fn population_v1(
+    state: State<AppState>,
+    addr: ConnectInfo<SocketAddr>,
+    format: ResponseFormat,
+    Query(params): Query<SearchParams>,
+) -> Result<Response, AppError> {
+    population(state, addr, format, Json(params.into())).await
+}
+
+/// Return the speeches containing the search term made by members of one category
+/// of a breakdown type.
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/speeches/{breakdown}/{id}",
+    params(
+        ("breakdown" = String, Path, description = "One of `party`, `gender`, `province`, `class`, or `speaker`"),
+        ("id" = i32, Path, description = "The id of the category, or of the speaker"),
+        SearchParams,
+    ),
+    responses(
+        (status = 200, description = "Matching speeches", body = [SpeechResponse]),
+        (status = 204, description = "The request was cancelled"),
+        (status = 400, description = "The search term or breakdown type is invalid", body = ErrorResponse),
+        (status = 404, description = "No category or speaker has the id", body = ErrorResponse),
+        (status = 429, description = "Too many requests, or too many queries running", body = ErrorResponse),
+        (status = 503, description = "The server is too busy", body = ErrorResponse),
+        (status = 504, description = "The query took too long", body = ErrorResponse),
+    )
+)]
+pub async fn speeches_v1(
+    path: Path<(String, i32)>,
+    state: State<AppState>,
+    addr: ConnectInfo<SocketAddr>,
+    Query(params): Query<SearchParams>,
+) -> Result<Json<Vec<SpeechResponse>>, AppError> {
+    speeches(path, state, addr, Json(params.into())).await
+}
@@ -0,0 +1,117 @@
+//! The audit log.
+//!
+//! Every request to an app route is logged as a single structured event when its
+//! response is ready, with the client's address, the route, how long it took, and
+//! its outcome. Handlers add what they know about the request as they handle it:
+//! the user, the search term, and the number of rows they responded with.
+//!
+//! Streamed responses are logged when the stream starts, without a row count.
+//!
+//! Chart queries are also recorded in the search analytics, anonymised.
+
+use crate::AppState;
+use axum::{
+    extract::{ConnectInfo, MatchedPath, Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::Response,
+};
+use common::models::DataRequest;
+use std::cell::RefCell;
+use std::net::SocketAddr;
+use tokio::time::Instant;
+use uuid::Uuid;
+
+tokio::task_local! {
+    /// What the handler of the request currently being handled has recorded.
+    static RECORD: RefCell<AuditRecord>;
+}
+
+/// What a handler recorded about its request.
+
+#[derive(Default)]
+struct AuditRecord {
+    uuid: Option<Uuid>,
+    search: Option<String>,
+    rows: Option<u64>,
+}
+
+/// Records the user and search term of a data request.
+
+pub fn record_request(payload: &DataRequest) {
+    record_user(payload.uuid);
+    record_search(&payload.search);
+}
+
+/// Records the user the request was made by.
+
+pub fn record_user(uuid: Uuid) {
+    let _ = RECORD.try_with(|record| record.borrow_mut().uuid = Some(uuid));
+}
+
+/// Records the search term of the request, as sent.
+
+pub fn record_search(search: &str) {
+    let _ = RECORD.try_with(|record| record.borrow_mut().search = Some(search.to_owned()));
+}
+
+/// Records the number of rows the request was responded to with.
+
+pub fn record_rows(rows: usize) {
+    let _ = RECORD.try_with(|record| record.borrow_mut().rows = Some(rows as u64));
+}
+
+/// Middleware that logs one audit event per request, once its response is ready.
+/// The outcome is `ok` for successful responses, `cancelled` for requests
+/// cancelled by the reaper, and `error` for everything else.
+///
+/// This is applied as a route layer, so only requests that matched a route are
+/// logged, not static files.
+
+pub async fn audit(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_owned())
+        .unwrap_or_default();
+    let path = request.uri().path().to_owned();
+    let start = Instant::now();
+
+    RECORD
+        .scope(RefCell::new(AuditRecord::default()), async move {
+            let response = next.run(request).await;
+            let record = RECORD.with(|record| record.take());
+            let uuid = record.uuid.map(|uuid| uuid.to_string());
+            let duration_ms = start.elapsed().as_millis() as u64;
+            let status = response.status();
+            let outcome = match status {
+                StatusCode::NO_CONTENT => "cancelled",
+                status if status.is_success() => "ok",
+                _ => "error",
+            };
+            if let Some(search) = &record.search {
+                let analytics = &state.analytics;
+                analytics.record(&path, search, addr.ip(), duration_ms, outcome);
+            }
+
+            tracing::info!(
+                target: "audit",
+                ip = %addr.ip(),
+                route = route.as_str(),
+                uuid = uuid.as_deref(),
+                search = record.search.as_deref(),
+                rows = record.rows,
+                duration_ms,
+                status = status.as_u16(),
+                outcome,
+                "request"
+            );
+            response
+        })
+        .await
+}
@@ -0,0 +1,315 @@
+//! Coalescing of identical concurrent queries.
+//!
+//! When many users search for the same word at once, their requests share a single
+//! database query. The first request for a query starts it in its own task, and
+//! requests that arrive while it's running wait on the same result. Queries that
+//! run in chunks report their partial results as they go, which streamed requests
+//! pass on as they arrive.
+
+use crate::error::AppError;
+use common::models::{BreakdownResponse, DispersionResponse, PopulationResponse};
+use metrics::counter;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+/// The result of the first `done` of the `total` chunks a shared query runs in.
+
+#[derive(Clone)]
+pub struct Partial<T> {
+    pub done: usize,
+    pub total: usize,
+    pub result: T,
+}
+
+/// The state of a shared query, as seen by the requests waiting on it.
+
+#[derive(Clone)]
+pub enum Update<T> {
+    /// The query is running, with its latest partial result if it has reported one.
+    Running(Option<Partial<T>>),
+    /// The query has finished.
+    Finished(Result<T, AppError>),
+}
+
+/// A shared query in flight.
+
+struct Flight<T> {
+    /// A unique id for this flight, so that a waiter never touches a later flight
+    /// for the same key.
+    id: Uuid,
+
+    /// A receiver for the query's updates, cloned for each waiter.
+    updates: watch::Receiver<Update<T>>,
+
+    /// The number of requests waiting on the query.
+    waiters: usize,
+
+    /// A token that stops the query, cancelled when the last waiter leaves.
+    abort: CancellationToken,
+}
+
+/// A handle a shared query reports its partial results through.
+
+pub struct Progress<T> {
+    sender: Arc<watch::Sender<Update<T>>>,
+}
+
+impl<T> Progress<T> {
+    /// Reports the result of the first `done` of the query's `total` chunks.
+
+    pub fn report(&self, done: usize, total: usize, result: T) {
+        self.sender.send_replace(Update::Running(Some(Partial {
+            done,
+            total,
+            result,
+        })));
+    }
+}
+
+/// A request waiting on a shared query, which receives its updates. Dropping the
+/// subscription leaves the query.
+
+pub struct Subscription<T> {
+    updates: watch::Receiver<Update<T>>,
+    _waiter: Waiter<T>,
+}
+
+impl<T: Clone> Subscription<T> {
+    /// Waits for the query's next update. The first update is the query's state
+    /// when the request joined it, returned straight away.
+
+    pub async fn next(&mut self) -> Result<Update<T>, AppError> {
+        self.updates
+            .changed()
+            .await
+            .map_err(|_| AppError::GenericError)?;
+        Ok(self.updates.borrow_and_update().clone())
+    }
+
+    /// Waits for the query's result.
+
+    pub async fn result(mut self) -> Result<T, AppError> {
+        let update = self
+            .updates
+            .wait_for(|update| matches!(update, Update::Finished(_)))
+            .await
+            .map_err(|_| AppError::GenericError)?;
+        match &*update {
+            Update::Finished(result) => result.clone(),
+            Update::Running(_) => Err(AppError::GenericError),
+        }
+    }
+}
+
+/// A set of shared queries returning the same type, keyed by the query and its
+/// search term.
+
+pub struct SingleFlight<T> {
+    flights: Arc<Mutex<HashMap<String, Flight<T>>>>,
+}
+
+impl<T> Clone for SingleFlight<T> {
+    fn clone(&self) -> Self {
+        SingleFlight {
+            flights: self.flights.clone(),
+        }
+    }
+}
+
+impl<T> Default for SingleFlight<T> {
+    fn default() -> Self {
+        SingleFlight {
+            flights: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl<T: Clone + Send + Sync + 'static> SingleFlight<T> {
+    /// Waits on the shared query with the given key, starting it if it isn't
+    /// already running. See `subscribe`.
+
+    pub async fn join<Q, F>(&self, key: String, query: Q) -> Result<T, AppError>
+    where
+        Q: FnOnce(Progress<T>) -> F,
+        F: Future<Output = Result<T, AppError>> + Send + 'static,
+    {
+        self.subscribe(key, query)?.result().await
+    }
+
+    /// Subscribes to the shared query with the given key, starting it with the
+    /// future the given function returns if it isn't already running. The function
+    /// is given a handle to report the query's partial results through.
+    ///
+    /// Dropping the subscription leaves the query. The query is stopped once every
+    /// waiter has left, which drops its future, so it should kill its database
+    /// query when dropped.
+
+    pub fn subscribe<Q, F>(&self, key: String, query: Q) -> Result<Subscription<T>, AppError>
+    where
+        Q: FnOnce(Progress<T>) -> F,
+        F: Future<Output = Result<T, AppError>> + Send + 'static,
+    {
+        let mut flights = self.flights.lock().map_err(|_| AppError::GenericError)?;
+        let (mut updates, id) = match flights.get_mut(&key) {
+            Some(flight) => {
+                counter!("coalesced_requests_total", "outcome" => "joined").increment(1);
+                flight.waiters += 1;
+                (flight.updates.clone(), flight.id)
+            }
+            None => {
+                counter!("coalesced_requests_total", "outcome" => "started").increment(1);
+                let id = Uuid::new_v4();
+                let (sender, receiver) = watch::channel(Update::Running(None));
+                let sender = Arc::new(sender);
+                let abort = CancellationToken::new();
+                flights.insert(
+                    key.clone(),
+                    Flight {
+                        id,
+                        updates: receiver.clone(),
+                        waiters: 1,
+                        abort: abort.clone(),
+                    },
+                );
+
+                let query = query(Progress {
+                    sender: sender.clone(),
+                });
+                let single_flight = self.clone();
+                let flight_key = key.clone();
+                tokio::spawn(async move {
+                    let res = tokio::select! {
+                        res = query => res,
+                        _ = abort.cancelled() => Err(AppError::Cancelled),
+                    };
+                    single_flight.land(&flight_key, id);
+                    sender.send_replace(Update::Finished(res));
+                });
+
+                (receiver, id)
+            }
+        };
+
+        updates.mark_changed();
+        Ok(Subscription {
+            updates,
+            _waiter: self.waiter(&key, id),
+        })
+    }
+
+    /// Returns a guard that leaves the given flight when dropped.
+
+    fn waiter(&self, key: &str, id: Uuid) -> Waiter<T> {
+        Waiter {
+            single_flight: self.clone(),
+            key: key.to_owned(),
+            id,
+        }
+    }
+
+    /// Forgets a finished flight, so that later requests start a new query.
+
+    fn land(&self, key: &str, id: Uuid) {
+        if let Ok(mut flights) = self.flights.lock() {
+            if flights.get(key).is_some_and(|flight| flight.id == id) {
+                flights.remove(key);
+            }
+        }
+    }
+}
+
+/// A request waiting on a flight. When dropped, the request leaves the flight, and
+/// the flight's query is stopped if no other requests are waiting on it.
+
+struct Waiter<T> {
+    single_flight: SingleFlight<T>,
+    key: String,
+    id: Uuid,
+}
+
+impl<T> Drop for Waiter<T> {
+    fn drop(&mut self) {
+        let Ok(mut flights) = self.single_flight.flights.lock() else {
+            return;
+        };
+        let Some(flight) = flights.get_mut(&self.key) else {
+            return;
+        };
+        if flight.id != self.id {
+            return;
+        }
+
+        flight.waiters -= 1;
+        if flight.waiters == 0 {
+            flight.abort.cancel();
+            flights.remove(&self.key);
+        }
+    }
+}
+
+/// The shared queries of each data route.
+
+#[derive(Clone, Default)]
+pub struct Flights {
+    pub breakdown: SingleFlight<Vec<BreakdownResponse>>,
+    pub dispersion: SingleFlight<Vec<DispersionResponse>>,
+    pub population: SingleFlight<Vec<PopulationResponse>>,
+}
+
+/// Returns the key of a shared query for a chart, named by its api path, and a
+/// normalised search term.
+
+pub fn flight_key(chart: &str, search: &str) -> String {
+    format!("{chart}\n{search}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::oneshot;
+
+    /// Subscribes to a query that runs until it's stopped, which drops the sender.
+
+    fn subscribe(
+        single_flight: &SingleFlight<u32>,
+        running: oneshot::Sender<()>,
+    ) -> Subscription<u32> {
+        let query = |_| async move {
+            let _running = running;
+            std::future::pending().await
+        };
+        let Ok(subscription) = single_flight.subscribe(flight_key("population", "gaza"), query)
+        else {
+            panic!("couldn't subscribe to the query");
+        };
+        subscription
+    }
+
+    fn waiters(single_flight: &SingleFlight<u32>) -> Option<usize> {
+        let flights = single_flight.flights.lock().unwrap();
+        flights.values().next().map(|flight| flight.waiters)
+    }
+
+    #[tokio::test]
+    async fn query_stops_when_the_last_waiter_leaves() {
+        let single_flight = SingleFlight::default();
+        let (running, mut stopped) = oneshot::channel();
+        let first = subscribe(&single_flight, running);
+        // the second request joins the first's query, so its own is never started
+        let second = subscribe(&single_flight, oneshot::channel().0);
+        assert_eq!(waiters(&single_flight), Some(2));
+
+        drop(first);
+        tokio::task::yield_now().await;
+        assert_eq!(waiters(&single_flight), Some(1));
+        assert_eq!(stopped.try_recv(), Err(oneshot::error::TryRecvError::Empty));
+
+        drop(second);
+        assert_eq!(waiters(&single_flight), None);
+        assert!(stopped.await.is_err());
+    }
+}
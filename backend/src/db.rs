@@ -1,5 +1,7 @@
+use crate::config::{DatabaseConfig, PoolConfig};
 use crate::error::AppError;
 use common::models::*;
+use common::search::escape_like;
 use db::class::dsl::{
     class, colour as class_colour, id as class_id, name as class_name,
     total_words as class_total_words,
@@ -20,9 +22,11 @@ use db::riding::dsl::{area, name as riding_name, population, riding};
 use db::speaker::dsl::{
     first_name, id as speaker_id, last_name, speaker, total_words as speaker_total_words,
 };
-use db::speech::dsl::{end, speaker as speech_speaker, speech, start, text};
+use db::speech::dsl::{
+    end, speaker as speech_speaker, speech, start, text, transcript as speech_transcript,
+};
 use db::speech_clean::dsl::{speech_clean, text as clean_text};
-use db::transcript::dsl::{link, transcript};
+use db::transcript::dsl::{id as transcript_id, link, transcript};
 use db::{concat, count_words, score};
 use diesel::dsl::sum;
 use diesel::prelude::*;
@@ -32,8 +36,7 @@ use diesel_async::{
     pooled_connection::{bb8::Pool, AsyncDieselConnectionManager},
     AsyncMysqlConnection, RunQueryDsl,
 };
-use dotenvy::dotenv;
-use std::env;
+use std::time::Duration;
 
 #[derive(QueryableByName)]
 struct ConnectionId {
@@ -41,30 +44,88 @@ struct ConnectionId {
     id: i32,
 }
 
-/// Returns an initialized bb8 async connection pool for the database.
-///
-/// The connection pool will retain 10 idle connections and will open at max
-/// 50 connections.
+/// An inclusive range of transcript ids.
 ///
-/// Panics on error, since there's no point in trying to run without database
-/// connections.
+/// Chart queries run over one range of the corpus at a time, so that their partial
+/// results can be streamed to the frontend as each range is processed.
+
+#[derive(Clone, Copy, Debug)]
+pub struct TranscriptRange {
+    pub first: i32,
+    pub last: i32,
+}
+
+/// The user-defined functions the chart queries need, with a call that fails if the
+/// function doesn't exist.
+
+const REQUIRED_FUNCTIONS: &[(&str, &str)] = &[
+    ("count_words", "count_words('word', 'word')"),
+    ("score", "score(1, 1)"),
+];
+
+/// Builds the connection pool for the database and checks that it's usable: that a
+/// connection can be checked out, and that the required functions exist. Returns a
+/// message describing the problem otherwise, so that the backend can refuse to
+/// start with a clear error instead of failing on every query.
+
+pub async fn connect(
+    database: &DatabaseConfig,
+    pool: &PoolConfig,
+) -> Result<Pool<AsyncMysqlConnection>, String> {
+    let pool = build_connection_pool(database, pool)
+        .await
+        .map_err(|error| format!("couldn't connect to the database: {error}"))?;
+    let mut connection = pool
+        .get()
+        .await
+        .map_err(|error| format!("couldn't check out a database connection: {error}"))?;
+    let missing = get_missing_functions(&mut connection).await;
+    if !missing.is_empty() {
+        return Err(format!(
+            "the database is missing the functions: {}",
+            missing.join(", ")
+        ));
+    }
+    drop(connection);
+    Ok(pool)
+}
 
-pub async fn get_connection_pool() -> Pool<AsyncMysqlConnection> {
-    dotenv().ok();
+/// Returns an initialized bb8 async connection pool for the database, or the error
+/// from opening its first connections.
 
-    let database_url = format!(
-        "{}{}",
-        env::var("DATABASE_URL").expect("DATABASE_URL must be set"),
-        env::var("DATA_SOURCE").expect("DATA_SOURCE must be set")
-    );
+pub async fn build_connection_pool(
+    database: &DatabaseConfig,
+    pool: &PoolConfig,
+) -> Result<Pool<AsyncMysqlConnection>, String> {
+    let database_url = database
+        .database_url()
+        .ok_or("DATABASE_URL and DATA_SOURCE must be set")?;
     let manager = AsyncDieselConnectionManager::<AsyncMysqlConnection>::new(database_url);
 
     Pool::builder()
-        .max_size(50) // todo find a sane default number here
-        .min_idle(Some(10)) // todo same
+        .max_size(pool.max_size)
+        .min_idle(Some(pool.min_idle))
+        .connection_timeout(Duration::from_secs(pool.connection_timeout))
         .build(manager)
         .await
-        .expect("Could not build connection pool")
+        .map_err(|error| error.to_string())
+}
+
+/// Returns the names of the required user-defined functions that can't be called
+/// on the connection.
+
+pub async fn get_missing_functions(connection: &mut AsyncMysqlConnection) -> Vec<&'static str> {
+    let mut missing = vec![];
+    for (name, call) in REQUIRED_FUNCTIONS {
+        let check = sql_query(format!("SELECT {call};"))
+            .execute(connection)
+            .await;
+        if let Err(error) = check {
+            tracing::warn!("couldn't call {name}: {error}");
+            missing.push(*name);
+        }
+    }
+    missing
 }
 
 /// Returns the MariaDB connection id, as an i32, that corresponds to the connection
@@ -93,6 +154,34 @@ pub async fn kill_connection_id(
     Ok(())
 }
 
+/// Returns the ids of all transcripts in the database split into at most the given
+/// number of ranges of equal size. Returns no ranges if there are no transcripts.
+
+pub async fn get_transcript_ranges(
+    connection: &mut AsyncMysqlConnection,
+    chunks: i32,
+) -> Result<Vec<TranscriptRange>, AppError> {
+    let (Some(first), Some(last)) = transcript
+        .select((
+            diesel::dsl::min(transcript_id),
+            diesel::dsl::max(transcript_id),
+        ))
+        .get_result::<(Option<i32>, Option<i32>)>(connection)
+        .await?
+    else {
+        return Ok(vec![]);
+    };
+
+    let size = (last - first) / chunks.max(1) + 1;
+    Ok((first..=last)
+        .step_by(size as usize)
+        .map(|start_id| TranscriptRange {
+            first: start_id,
+            last: last.min(start_id + (size - 1)),
+        })
+        .collect())
+}
+
 /// Returns all the speakers in the database, as SpeakerResponse objects.
 
 pub async fn get_speakers(
@@ -113,15 +202,21 @@ pub async fn get_speakers(
 /// id, gender id,  etc.), the breakdown name, the breakdown colour, the sum of all times
 /// that the word is mentioned for that breakdown, and that sum adjusted to the number of
 /// words spoken in total.
+///
+/// Only speeches in the given range of transcripts are counted. Every speaker is
+/// returned for the speaker breakdown, so that the top speakers can be found once
+/// the counts of every range are summed.
 
 pub async fn get_breakdown_word_count(
     connection: &mut AsyncMysqlConnection,
     breakdown_type: BreakdownType,
     word: &str,
+    transcripts: TranscriptRange,
 ) -> Result<Vec<BreakdownResponse>, AppError> {
+    let in_range = speech_transcript.between(transcripts.first, transcripts.last);
     let loaded = match breakdown_type {
         BreakdownType::Party => speech
-            .filter(party_total_words.gt(0))
+            .filter(party_total_words.gt(0).and(in_range))
             .inner_join(speaker.inner_join(party))
             .group_by((party_id, party_name, party_colour, party_total_words))
             .select((
@@ -133,7 +228,7 @@ pub async fn get_breakdown_word_count(
             ))
             .load::<BreakdownRow>(connection),
         BreakdownType::Gender => speech
-            .filter(gender_total_words.gt(0))
+            .filter(gender_total_words.gt(0).and(in_range))
             .inner_join(speaker.inner_join(gender))
             .group_by((gender_id, gender_name, gender_colour, gender_total_words))
             .select((
@@ -145,7 +240,7 @@ pub async fn get_breakdown_word_count(
             ))
             .load::<BreakdownRow>(connection),
         BreakdownType::Province => speech
-            .filter(province_total_words.gt(0))
+            .filter(province_total_words.gt(0).and(in_range))
             .inner_join(speaker.inner_join(province))
             .group_by((
                 province_id,
@@ -162,7 +257,7 @@ pub async fn get_breakdown_word_count(
             ))
             .load::<BreakdownRow>(connection),
         BreakdownType::Class => speech
-            .filter(class_total_words.gt(0))
+            .filter(class_total_words.gt(0).and(in_range))
             .inner_join(speaker.inner_join(class))
             .group_by((class_id, class_name, class_colour, class_total_words))
             .select((
@@ -174,7 +269,7 @@ pub async fn get_breakdown_word_count(
             ))
             .load::<BreakdownRow>(connection),
         BreakdownType::Speaker => speech
-            .filter(speaker_total_words.gt(0))
+            .filter(speaker_total_words.gt(0).and(in_range))
             .inner_join(speaker.inner_join(party))
             .group_by((
                 speaker_id,
@@ -190,8 +285,6 @@ pub async fn get_breakdown_word_count(
                 sum(count_words(text, word)),
                 score(speaker_total_words, sum(count_words(text, word))),
             ))
-            .order(sum(count_words(text, word)).desc())
-            .limit(10)
             .load::<BreakdownRow>(connection),
     };
 
@@ -202,19 +295,130 @@ pub async fn get_breakdown_word_count(
         .collect())
 }
 
+/// Returns per-speaker data for the given word within each category of the given
+/// breakdown type (party, gender, etc.).
+///
+/// The return type is DispersionResponse, which contains the id of the breakdown
+/// category the speaker belongs to, the speaker id, their full name, their party
+/// colour, the sum of all times they mentioned the word, and that sum adjusted to the
+/// number of words they spoke in total. The speaker breakdown has no categories to
+/// disperse, so requesting it returns `BadBreakdownType`.
+
+pub async fn get_breakdown_dispersion(
+    connection: &mut AsyncMysqlConnection,
+    breakdown_type: BreakdownType,
+    word: &str,
+) -> Result<Vec<DispersionResponse>, AppError> {
+    let loaded = match breakdown_type {
+        BreakdownType::Party => speech
+            .filter(speaker_total_words.gt(0))
+            .inner_join(speaker.inner_join(party))
+            .group_by((
+                party_id,
+                speaker_id,
+                first_name,
+                last_name,
+                party_colour,
+                speaker_total_words,
+            ))
+            .select((
+                party_id,
+                speaker_id,
+                concat(first_name, " ", last_name),
+                party_colour,
+                sum(count_words(text, word)),
+                score(speaker_total_words, sum(count_words(text, word))),
+            ))
+            .load::<DispersionRow>(connection),
+        BreakdownType::Gender => speech
+            .filter(speaker_total_words.gt(0))
+            .inner_join(speaker.inner_join(party).inner_join(gender))
+            .group_by((
+                gender_id,
+                speaker_id,
+                first_name,
+                last_name,
+                party_colour,
+                speaker_total_words,
+            ))
+            .select((
+                gender_id,
+                speaker_id,
+                concat(first_name, " ", last_name),
+                party_colour,
+                sum(count_words(text, word)),
+                score(speaker_total_words, sum(count_words(text, word))),
+            ))
+            .load::<DispersionRow>(connection),
+        BreakdownType::Province => speech
+            .filter(speaker_total_words.gt(0))
+            .inner_join(speaker.inner_join(party).inner_join(province))
+            .group_by((
+                province_id,
+                speaker_id,
+                first_name,
+                last_name,
+                party_colour,
+                speaker_total_words,
+            ))
+            .select((
+                province_id,
+                speaker_id,
+                concat(first_name, " ", last_name),
+                party_colour,
+                sum(count_words(text, word)),
+                score(speaker_total_words, sum(count_words(text, word))),
+            ))
+            .load::<DispersionRow>(connection),
+        BreakdownType::Class => speech
+            .filter(speaker_total_words.gt(0))
+            .inner_join(speaker.inner_join(party).inner_join(class))
+            .group_by((
+                class_id,
+                speaker_id,
+                first_name,
+                last_name,
+                party_colour,
+                speaker_total_words,
+            ))
+            .select((
+                class_id,
+                speaker_id,
+                concat(first_name, " ", last_name),
+                party_colour,
+                sum(count_words(text, word)),
+                score(speaker_total_words, sum(count_words(text, word))),
+            ))
+            .load::<DispersionRow>(connection),
+        BreakdownType::Speaker => return Err(AppError::BadBreakdownType),
+    };
+
+    Ok(loaded
+        .await?
+        .into_iter()
+        .filter_map(to_dispersion_response)
+        .collect())
+}
+
 /// Returns riding population density data for the given word.
 ///
 /// The return type is PopulationResponse, which contains the id of the speaker, the
 /// riding name, the riding population, the riding area, the party colour, the sum of
 /// all times that the word is mentioned for that breakdown, and that sum adjusted to
-/// the number of words spoken in total.
+/// the number of words spoken in total. Only speeches in the given range of
+/// transcripts are counted.
 
 pub async fn get_population_word_count(
     connection: &mut AsyncMysqlConnection,
     word: &str,
+    transcripts: TranscriptRange,
 ) -> Result<Vec<PopulationResponse>, AppError> {
     Ok(speech
-        .filter(speaker_total_words.gt(0))
+        .filter(
+            speaker_total_words
+                .gt(0)
+                .and(speech_transcript.between(transcripts.first, transcripts.last)),
+        )
         .inner_join(speaker.inner_join(party).inner_join(riding))
         .group_by((
             speaker_id,
@@ -251,7 +455,8 @@ pub async fn get_speeches(
     id: i32,
     word: &str,
 ) -> Result<Vec<SpeechResponse>, AppError> {
-    let word = format!("%{}%", word);
+    // backslash is mysql's default LIKE escape character
+    let word = format!("%{}%", escape_like(word));
     let loaded = match breakdown_type {
         BreakdownType::Party => speech
             .inner_join(speech_clean)
@@ -296,3 +501,52 @@ pub async fn get_speeches(
 
     Ok(loaded.await?.into_iter().map(|row| row.into()).collect())
 }
+
+/// Returns whether a category of the given breakdown type, or a speaker, exists
+/// with the given id.
+
+pub async fn id_exists(
+    connection: &mut AsyncMysqlConnection,
+    breakdown_type: BreakdownType,
+    id: i32,
+) -> Result<bool, AppError> {
+    let count = match breakdown_type {
+        BreakdownType::Party => {
+            party
+                .filter(party_id.eq(id))
+                .count()
+                .get_result::<i64>(connection)
+                .await?
+        }
+        BreakdownType::Gender => {
+            gender
+                .filter(gender_id.eq(id))
+                .count()
+                .get_result::<i64>(connection)
+                .await?
+        }
+        BreakdownType::Province => {
+            province
+                .filter(province_id.eq(id))
+                .count()
+                .get_result::<i64>(connection)
+                .await?
+        }
+        BreakdownType::Class => {
+            class
+                .filter(class_id.eq(id))
+                .count()
+                .get_result::<i64>(connection)
+                .await?
+        }
+        BreakdownType::Speaker => {
+            speaker
+                .filter(speaker_id.eq(id))
+                .count()
+                .get_result::<i64>(connection)
+                .await?
+        }
+    };
+
+    Ok(count > 0)
+}
@@ -0,0 +1,48 @@
+//! Demo datasets for the info page.
+//!
+//! Each data source has a directory of json files under the demo directory, e.g.
+//! `demo/federal_house/pipeline-party.json`, served by name at
+//! `/api/demo/pipeline-party`. Demos are read from disk on each request, so they
+//! can be added or updated without rebuilding the frontend or restarting the
+//! backend.
+
+use crate::error::AppError;
+use crate::AppState;
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use serde_json::Value;
+use std::io::ErrorKind;
+
+/// Returns whether a demo name is safe to use as a file name: only lowercase
+/// letters, digits, dashes, and underscores.
+
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
+
+/// Return the demo dataset with the given name, for the current data source.
+/// Returns status 404 if there's no such demo.
+
+pub async fn demo(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    if !valid_name(&name) {
+        return Err(AppError::UnknownDemo);
+    }
+    let path = state.demo_dir.join(format!("{name}.json"));
+    let text = match tokio::fs::read_to_string(&path).await {
+        Ok(text) => text,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Err(AppError::UnknownDemo),
+        Err(error) => return Err(error.into()),
+    };
+    serde_json::from_str(&text).map(Json).map_err(|error| {
+        tracing::error!("demo {} isn't valid json: {error}", path.display());
+        error.into()
+    })
+}
@@ -2041,6 +2041,34 @@ pub fn dummy_get_breakdown_word_count(breakdown_type: BreakdownType) -> Vec<Brea
     }
 }
 
+/// Return a dummy set of per-speaker data for the given breakdown chart type.
+///
+/// The dummy speakers are dealt out across the categories of the dummy breakdown
+/// data, with made up but stable counts and scores.
+
+pub fn dummy_get_breakdown_dispersion(breakdown_type: BreakdownType) -> Vec<DispersionResponse> {
+    if breakdown_type == BreakdownType::Speaker {
+        return vec![];
+    }
+
+    let categories = dummy_get_breakdown_word_count(breakdown_type);
+    dummy_get_speakers()
+        .into_iter()
+        .map(|s| {
+            let category = &categories[s.id as usize % categories.len()];
+            let count = ((s.id * 7919) % 23) as i64;
+            DispersionResponse {
+                breakdown_id: category.id,
+                id: s.id,
+                name: format!("{} {}", s.first_name, s.last_name),
+                colour: category.colour.clone(),
+                count,
+                score: count as f64 * category.score / 6.0,
+            }
+        })
+        .collect()
+}
+
 /// Return a dummy set of data for the population density scatterplot.
 ///
 /// This data is roughly the same as the demonstration in the info page.
@@ -1,16 +1,33 @@
 use axum::{
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{IntoResponse, Response},
+    Json,
 };
-use common::models::BreakdownTypeParseError;
+use common::export::ExportError;
+use common::models::{BreakdownTypeParseError, ErrorCode, ErrorResponse};
+use common::search::SearchError;
 use diesel::result::Error as DieselError;
 use diesel_async::pooled_connection::bb8::RunError;
+use plotting::ChartError;
 use tokio::sync::mpsc::error::SendError;
+use tokio::sync::oneshot::error::RecvError;
+use tokio::task::JoinError;
 
 use crate::reaper::Message;
+use crate::request_id::current_request_id;
 
-/// An error type for the backend
+/// The number of seconds clients are asked to wait before retrying when all
+/// database connections are busy, or they have too many queries running.
 
+const BUSY_RETRY_AFTER: u64 = 5;
+
+/// An error type for the backend.
+///
+/// Every error except `Cancelled` responds with a json `ErrorResponse` body,
+/// carrying a machine-readable code, a message for users, and the request id.
+/// Errors are cloneable so that requests sharing a query can share its error.
+
+#[derive(Clone)]
 pub enum AppError {
     /// A generic, unrecoverable error. Translates to http status code `500
     /// Internal Server Error`.
@@ -23,8 +40,8 @@ pub enum AppError {
     /// Translates to http status code `503 Service Unavailable`.
     ///
     /// This error probably means the server is getting too many requests and/or
-    /// the database has slowed to a crawl. For now it also causes charts to
-    /// enter the same fail state as the GenericError.
+    /// the database has slowed to a crawl. Clients are told to retry after
+    /// `BUSY_RETRY_AFTER` seconds in a `Retry-After` header.
     ConnectionPoolError,
 
     /// An error indicating the handler was cancelled by the reaper. Translates to
@@ -34,29 +51,196 @@ pub enum AppError {
     /// request, and the server was asked to kill existing queries. A chart that
     /// receives the corresponding status code `204` will not enter a fail state.
     Cancelled,
+
+    /// An error indicating the client has hit the rate limit, holding the number
+    /// of seconds until it can try again. Translates to http status code `429 Too
+    /// Many Requests`, with a `Retry-After` header.
+    RateLimited(u64),
+
+    /// An error indicating the breakdown type or chart in the url doesn't exist.
+    /// Translates to http status code `400 Bad Request`.
+    BadBreakdownType,
+
+    /// An error indicating the search term is empty, too long, or contains
+    /// characters that can't be searched for. Translates to http status code `400
+    /// Bad Request`.
+    BadSearch(SearchError),
+
+    /// An error indicating no category or speaker has the requested id. Translates
+    /// to http status code `404 Not Found`.
+    UnknownId,
+
+    /// An error indicating there's no demo dataset with the requested name.
+    /// Translates to http status code `404 Not Found`.
+    UnknownDemo,
+
+    /// An error indicating an admin route was requested without the admin token,
+    /// or with no admin token configured. Translates to http status code `401
+    /// Unauthorized`.
+    Unauthorized,
+
+    /// An error indicating the request sent an api key that isn't on the allow-list.
+    /// Translates to http status code `401 Unauthorized`.
+    BadApiKey,
+
+    /// An error indicating the reaper refused a query because the user or their ip
+    /// address already has too many queries running. Translates to http status
+    /// code `429 Too Many Requests`, with a `Retry-After` header.
+    TooManyQueries,
+
+    /// An error indicating a query ran for longer than the query timeout, and was
+    /// killed by the reaper. Translates to http status code `504 Gateway Timeout`.
+    Timeout,
 }
 
-// note: the message is unused at this time
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        let (status, message) = match self {
-            AppError::Cancelled => (StatusCode::NO_CONTENT, "request cancelled".to_owned()),
+impl AppError {
+    /// Returns the http status code of this error and the json body describing it,
+    /// or None for `Cancelled`, which has no body.
+
+    pub fn status_and_body(&self) -> Option<(StatusCode, ErrorResponse)> {
+        let (status, code, message) = match self {
+            AppError::Cancelled => return None,
             AppError::ConnectionPoolError => (
                 StatusCode::SERVICE_UNAVAILABLE,
+                ErrorCode::Busy,
                 "our servers are very busy - please try again later".to_owned(),
             ),
             AppError::GenericError => (
                 StatusCode::INTERNAL_SERVER_ERROR,
+                ErrorCode::Internal,
                 "something went wrong - please refresh the page and try again".to_owned(),
             ),
+            AppError::RateLimited(_) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                ErrorCode::RateLimited,
+                "too many requests - please slow down".to_owned(),
+            ),
+            AppError::BadBreakdownType => (
+                StatusCode::BAD_REQUEST,
+                ErrorCode::BadBreakdownType,
+                "there's no chart of that type".to_owned(),
+            ),
+            AppError::BadSearch(error) => (
+                StatusCode::BAD_REQUEST,
+                match error {
+                    SearchError::Empty => ErrorCode::EmptySearch,
+                    SearchError::TooLong => ErrorCode::SearchTooLong,
+                    SearchError::BadCharacter(_) => ErrorCode::InvalidSearch,
+                },
+                error.to_string(),
+            ),
+            AppError::UnknownId => (
+                StatusCode::NOT_FOUND,
+                ErrorCode::UnknownId,
+                "we couldn't find what you clicked on - please try searching again".to_owned(),
+            ),
+            AppError::UnknownDemo => (
+                StatusCode::NOT_FOUND,
+                ErrorCode::UnknownDemo,
+                "there's no demo with that name".to_owned(),
+            ),
+            AppError::Unauthorized => (
+                StatusCode::UNAUTHORIZED,
+                ErrorCode::Unauthorized,
+                "a valid admin token is required".to_owned(),
+            ),
+            AppError::BadApiKey => (
+                StatusCode::UNAUTHORIZED,
+                ErrorCode::BadApiKey,
+                "the api key isn't recognised".to_owned(),
+            ),
+            AppError::TooManyQueries => (
+                StatusCode::TOO_MANY_REQUESTS,
+                ErrorCode::TooManyQueries,
+                "you have too many searches running - please wait for them to finish".to_owned(),
+            ),
+            AppError::Timeout => (
+                StatusCode::GATEWAY_TIMEOUT,
+                ErrorCode::Timeout,
+                "this search took too long - please try a more specific search term".to_owned(),
+            ),
+        };
+
+        let retry_after = match self {
+            AppError::RateLimited(wait) => Some(*wait),
+            AppError::ConnectionPoolError | AppError::TooManyQueries => Some(BUSY_RETRY_AFTER),
+            _ => None,
         };
 
-        (status, message).into_response()
+        Some((
+            status,
+            ErrorResponse {
+                code,
+                message,
+                retry_after,
+                request_id: current_request_id(),
+            },
+        ))
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let Some((status, body)) = self.status_and_body() else {
+            return StatusCode::NO_CONTENT.into_response();
+        };
+
+        match body.retry_after {
+            Some(wait) => (
+                status,
+                [(header::RETRY_AFTER, wait.to_string())],
+                Json(body),
+            )
+                .into_response(),
+            None => (status, Json(body)).into_response(),
+        }
     }
 }
 
 impl From<BreakdownTypeParseError> for AppError {
     fn from(_: BreakdownTypeParseError) -> Self {
+        Self::BadBreakdownType
+    }
+}
+
+impl From<SearchError> for AppError {
+    fn from(error: SearchError) -> Self {
+        Self::BadSearch(error)
+    }
+}
+
+impl From<ExportError> for AppError {
+    fn from(_: ExportError) -> Self {
+        Self::GenericError
+    }
+}
+
+impl From<time::error::Format> for AppError {
+    fn from(_: time::error::Format) -> Self {
+        Self::GenericError
+    }
+}
+
+impl From<ChartError> for AppError {
+    fn from(_: ChartError) -> Self {
+        Self::GenericError
+    }
+}
+
+impl From<png::EncodingError> for AppError {
+    fn from(_: png::EncodingError) -> Self {
+        Self::GenericError
+    }
+}
+
+impl From<JoinError> for AppError {
+    fn from(_: JoinError) -> Self {
+        Self::GenericError
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(_: std::io::Error) -> Self {
         Self::GenericError
     }
 }
@@ -67,6 +251,18 @@ impl From<SendError<Message>> for AppError {
     }
 }
 
+impl From<RecvError> for AppError {
+    fn from(_: RecvError) -> Self {
+        Self::GenericError
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(_: serde_json::Error) -> Self {
+        Self::GenericError
+    }
+}
+
 impl From<DieselError> for AppError {
     fn from(_: DieselError) -> Self {
         Self::GenericError
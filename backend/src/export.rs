@@ -0,0 +1,72 @@
+use crate::audit;
+use crate::error::AppError;
+use axum::{
+    extract::FromRequestParts,
+    http::{header, request::Parts},
+    response::{IntoResponse, Response},
+    Json,
+};
+use common::export::{to_csv, ExportMeta};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::convert::Infallible;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+/// The format a data route responds in, negotiated from the request's `Accept`
+/// header.
+
+pub enum ResponseFormat {
+    /// A plain json array of rows. This is the default.
+    Json,
+
+    /// A csv file with the search term, data source, and generation time in a
+    /// comment header. Requested with `Accept: text/csv`.
+    Csv,
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ResponseFormat {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let csv = parts
+            .headers
+            .get(header::ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            .is_some_and(|accept| accept.contains("text/csv"));
+
+        Ok(if csv {
+            ResponseFormat::Csv
+        } else {
+            ResponseFormat::Json
+        })
+    }
+}
+
+impl ResponseFormat {
+    /// Returns a response containing the given rows in this format.
+
+    pub fn respond<T: Serialize + DeserializeOwned>(
+        self,
+        data: Vec<T>,
+        search: &str,
+        source: &str,
+    ) -> Result<Response, AppError> {
+        audit::record_rows(data.len());
+        match self {
+            ResponseFormat::Json => Ok(Json(data).into_response()),
+            ResponseFormat::Csv => {
+                let meta = ExportMeta {
+                    search: search.to_owned(),
+                    source: source.to_owned(),
+                    generated: OffsetDateTime::now_utc().format(&Rfc3339)?,
+                };
+                Ok((
+                    [(header::CONTENT_TYPE, "text/csv; charset=utf-8")],
+                    to_csv(&meta, &data)?,
+                )
+                    .into_response())
+            }
+        }
+    }
+}
@@ -1,15 +1,25 @@
+use crate::audit;
+use crate::coalesce::{flight_key, Progress, Subscription};
 use crate::db::*;
 use crate::dummy_db::*;
 use crate::error::AppError;
-use crate::reaper::{ActiveQuery, Message};
+use crate::export::ResponseFormat;
+use crate::reaper::{ActiveQuery, Connection, Message, Watch};
+use crate::render::*;
+use crate::stream::{merge, top_speakers};
 use crate::AppState;
 use axum::{
-    extract::{Path, State},
+    extract::{ConnectInfo, Path, Query, State},
+    response::{Html, Response},
     Json,
 };
 use common::models::*;
+use common::search::{normalise, validate};
+use plotting::breakdown::BreakdownChart;
+use plotting::population::PopulationChart;
+use std::net::SocketAddr;
 use std::str::FromStr;
-use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
 
 /// Return all speakers in the database.
 
@@ -18,7 +28,9 @@ pub async fn speakers(
 ) -> Result<Json<Vec<SpeakerResponse>>, AppError> {
     if let Some(pool) = state.connection_pool {
         let mut conn = pool.get().await?;
-        Ok(Json(get_speakers(&mut conn).await?))
+        let speakers = get_speakers(&mut conn).await?;
+        audit::record_rows(speakers.len());
+        Ok(Json(speakers))
     } else {
         Ok(Json(dummy_get_speakers()))
     }
@@ -27,165 +39,240 @@ pub async fn speakers(
 /// Return all speeches matching the given word and breakdown type. See db call for
 /// description of return columns.
 ///
-/// This handler registers a cancellation token with the reaper, and will return
-/// status 204 if cancelled.
+/// Precomputed results are returned straight away. Otherwise, this handler shares
+/// its query with identical concurrent requests, registers with the reaper, and
+/// will return status 204 if cancelled, 429 if too many queries are running, or 504
+/// if the query times out. Responds with csv if requested in the `Accept` header.
 
 pub async fn breakdown(
     State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     Path(breakdown_type): Path<String>,
+    format: ResponseFormat,
     Json(payload): Json<DataRequest>,
-) -> Result<Json<Vec<BreakdownResponse>>, AppError> {
-    tracing::info!("{payload}");
+) -> Result<Response, AppError> {
+    audit::record_request(&payload);
+    let search = checked_search(&payload.search)?;
     let breakdown_type = BreakdownType::from_str(breakdown_type.as_str())?;
-    if let Some(pool) = state.connection_pool {
-        let mut conn = pool.get().await?;
-        let conn_id = get_connection_id(&mut conn).await?;
-
-        let token = CancellationToken::new();
-
-        state
-            .sender
-            .send(Message::Register((
-                ActiveQuery {
-                    uuid: payload.uuid.clone(),
-                    conn_id,
-                    speech: false,
-                },
-                token.clone(),
-            )))
+    if state.connection_pool.is_some() {
+        let chart = format!("breakdown/{breakdown_type}");
+        if let Some(rows) = state.results.breakdown.get(&chart, &search) {
+            return format.respond(rows, &search, &state.data_source);
+        }
+        let watch = wait(&state, &payload, chart, addr).await?;
+        let rows = watch
+            .run(shared_breakdown(&state, breakdown_type, &search))
             .await?;
+        format.respond(rows, &search, &state.data_source)
+    } else {
+        format.respond(
+            dummy_get_breakdown_word_count(breakdown_type),
+            &search,
+            &state.data_source,
+        )
+    }
+}
 
-        let response = tokio::select! {
-            res = get_breakdown_word_count(&mut conn, breakdown_type, &payload.search) => {
-                Ok(Json(res?))
-            }
-            _ = token.cancelled() => {
-                Err(AppError::Cancelled)
-            }
-        };
+/// Return per-speaker data within each category of the given breakdown type for the
+/// given word. See db call for description of return columns.
+///
+/// Precomputed results are returned straight away. Otherwise, this handler shares
+/// its query with identical concurrent requests, registers with the reaper, and
+/// will return status 204 if cancelled, 429 if too many queries are running, or 504
+/// if the query times out. Responds with csv if requested in the `Accept` header.
 
-        // todo don't send this if we cancelled anyway
-        state
-            .sender
-            .send(Message::Deregister(ActiveQuery {
-                uuid: payload.uuid,
-                conn_id,
-                speech: false,
-            }))
+pub async fn dispersion(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(breakdown_type): Path<String>,
+    format: ResponseFormat,
+    Json(payload): Json<DataRequest>,
+) -> Result<Response, AppError> {
+    audit::record_request(&payload);
+    let search = checked_search(&payload.search)?;
+    let breakdown_type = BreakdownType::from_str(breakdown_type.as_str())?;
+    if state.connection_pool.is_some() {
+        let chart = format!("dispersion/{breakdown_type}");
+        if let Some(rows) = state.results.dispersion.get(&chart, &search) {
+            return format.respond(rows, &search, &state.data_source);
+        }
+        let watch = wait(&state, &payload, chart, addr).await?;
+        let rows = watch
+            .run(shared_dispersion(&state, breakdown_type, &search))
             .await?;
-
-        response
+        format.respond(rows, &search, &state.data_source)
     } else {
-        Ok(Json(dummy_get_breakdown_word_count(breakdown_type)))
+        format.respond(
+            dummy_get_breakdown_dispersion(breakdown_type),
+            &search,
+            &state.data_source,
+        )
     }
 }
 
 /// Return population data matching the given word. See db call for description of
 /// return columns.
 ///
-/// This handler registers a cancellation token with the reaper, and will return
-/// status 204 if cancelled.
+/// Precomputed results are returned straight away. Otherwise, this handler shares
+/// its query with identical concurrent requests, registers with the reaper, and
+/// will return status 204 if cancelled, 429 if too many queries are running, or 504
+/// if the query times out. Responds with csv if requested in the `Accept` header.
 
 pub async fn population(
     State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    format: ResponseFormat,
     Json(payload): Json<DataRequest>,
-) -> Result<Json<Vec<PopulationResponse>>, AppError> {
-    tracing::info!("{payload}");
-    if let Some(pool) = state.connection_pool {
-        let mut conn = pool.get().await?;
-        let conn_id = get_connection_id(&mut conn).await?;
-
-        let token = CancellationToken::new();
-
-        state
-            .sender
-            .send(Message::Register((
-                ActiveQuery {
-                    uuid: payload.uuid.clone(),
-                    conn_id,
-                    speech: false,
-                },
-                token.clone(),
-            )))
-            .await?;
-
-        let response = tokio::select! {
-            res = get_population_word_count(&mut conn, &payload.search) => {
-                Ok(Json(res?))
-            }
-            _ = token.cancelled() => {
-                Err(AppError::Cancelled)
-            }
-        };
-
-        state
-            .sender
-            .send(Message::Deregister(ActiveQuery {
-                uuid: payload.uuid,
-                conn_id,
-                speech: false,
-            }))
-            .await?;
-
-        response
+) -> Result<Response, AppError> {
+    audit::record_request(&payload);
+    let search = checked_search(&payload.search)?;
+    if state.connection_pool.is_some() {
+        let chart = "population".to_string();
+        if let Some(rows) = state.results.population.get(&chart, &search) {
+            return format.respond(rows, &search, &state.data_source);
+        }
+        let watch = wait(&state, &payload, chart, addr).await?;
+        let rows = watch.run(shared_population(&state, &search)).await?;
+        format.respond(rows, &search, &state.data_source)
     } else {
-        Ok(Json(dummy_get_population_word_count()))
+        format.respond(
+            dummy_get_population_word_count(),
+            &search,
+            &state.data_source,
+        )
     }
 }
 
 /// Return all speeches matching the given word, breakdown type, and id. See db call
 /// for description of return columns.
 ///
-/// This handler registers a cancellation token with the reaper, and will return
-/// status 204 if cancelled.
+/// This handler registers its query with the reaper, and will return status 204 if
+/// cancelled, 429 if too many queries are running, or 504 if the query times out.
+/// If no speeches are found, it checks that the id exists, and returns status 404
+/// if it doesn't.
 
 pub async fn speeches(
     Path((breakdown_type, id)): Path<(String, i32)>,
     State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     Json(payload): Json<DataRequest>,
 ) -> Result<Json<Vec<SpeechResponse>>, AppError> {
-    tracing::info!("{payload}");
-    if let Some(pool) = state.connection_pool {
-        let mut conn = pool.get().await?;
-        let conn_id = get_connection_id(&mut conn).await?;
-
-        let token = CancellationToken::new();
-
-        state
-            .sender
-            .send(Message::Register((
-                ActiveQuery {
-                    uuid: payload.uuid.clone(),
-                    conn_id,
-                    speech: true,
-                },
-                token.clone(),
-            )))
-            .await?;
+    audit::record_request(&payload);
+    let search = checked_search(&payload.search)?;
+    let breakdown_type = BreakdownType::from_str(breakdown_type.as_str())?;
+    if let Some(pool) = &state.connection_pool {
+        let conn = pool.get_owned().await?;
+        let chart = "speeches".to_string();
+        let watch = watch(&state, conn, &payload, chart, true, Some(addr)).await?;
+        watch
+            .run_on(move |conn| {
+                Box::pin(async move {
+                    match get_speeches(conn, breakdown_type.clone(), id, &search).await? {
+                        speeches
+                            if speeches.is_empty()
+                                && !id_exists(conn, breakdown_type, id).await? =>
+                        {
+                            Err(AppError::UnknownId)
+                        }
+                        speeches => {
+                            audit::record_rows(speeches.len());
+                            Ok(Json(speeches))
+                        }
+                    }
+                })
+            })
+            .await
+    } else {
+        return Ok(Json(dummy_get_speeches()));
+    }
+}
 
-        let breakdown_type = BreakdownType::from_str(breakdown_type.as_str())?;
+/// Render a breakdown chart or the population plot for the search term in the
+/// query as a png or svg image, for link previews and embeds. The file is the chart
+/// name and format, e.g. `party.png` or `population.svg`.
+///
+/// Images are drawn by the same plotting code as the frontend charts, from
+/// precomputed results if there are any. Their queries are watched by the reaper
+/// like those of the data routes, under a random uuid.
 
-        let response = tokio::select! {
-            res = get_speeches(&mut conn, breakdown_type, id, &payload.search) => {
-                Ok(Json(res?))
-            }
-            _ = token.cancelled() => {
-                Err(AppError::Cancelled)
-            }
-        };
+pub async fn render(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(file): Path<String>,
+    Query(params): Query<RenderRequest>,
+) -> Result<Response, AppError> {
+    audit::record_search(&params.search);
+    let search = checked_search(&params.search)?;
+    let RenderTarget { chart, format } = RenderTarget::from_str(file.as_str())?;
+    let caption = format!("\"{search}\" - {chart}");
+    let payload = DataRequest::new(Uuid::new_v4(), search.clone());
+    let watched = format!("render/{file}");
+    match chart {
+        RenderedChart::Breakdown(breakdown_type) => {
+            let precomputed = state
+                .results
+                .breakdown
+                .get(&format!("breakdown/{breakdown_type}"), &search);
+            let data = if let Some(rows) = precomputed {
+                rows
+            } else if state.connection_pool.is_some() {
+                let watch = wait(&state, &payload, watched, addr).await?;
+                watch
+                    .run(shared_breakdown(&state, breakdown_type.clone(), &search))
+                    .await?
+            } else {
+                dummy_get_breakdown_word_count(breakdown_type.clone())
+            };
 
-        state
-            .sender
-            .send(Message::Deregister(ActiveQuery {
-                uuid: payload.uuid,
-                conn_id,
-                speech: true,
-            }))
-            .await?;
+            tokio::task::spawn_blocking(move || {
+                let chart = BreakdownChart {
+                    breakdown_type: &breakdown_type,
+                    data: &data,
+                    dispersion: &[],
+                    show_counts: params.counts,
+                };
+                format.render(&chart, &caption, state.render_max_age)
+            })
+            .await?
+        }
+        RenderedChart::Population => {
+            let precomputed = state.results.population.get("population", &search);
+            let data = if let Some(rows) = precomputed {
+                rows
+            } else if state.connection_pool.is_some() {
+                let watch = wait(&state, &payload, watched, addr).await?;
+                watch.run(shared_population(&state, &search)).await?
+            } else {
+                dummy_get_population_word_count()
+            };
 
-        response
-    } else {
-        return Ok(Json(dummy_get_speeches()));
+            tokio::task::spawn_blocking(move || {
+                let chart = PopulationChart {
+                    data: &data,
+                    show_counts: params.counts,
+                };
+                format.render(&chart, &caption, state.render_max_age)
+            })
+            .await?
+        }
+    }
+}
+
+/// Serve the app for a shared search url. If the url has a search term, OpenGraph
+/// meta tags describing it are added so that link previews show a chart.
+
+pub async fn search_page(
+    State(state): State<AppState>,
+    Query(params): Query<ShareRequest>,
+) -> Result<Html<String>, AppError> {
+    let index = tokio::fs::read_to_string(state.static_dir.join("index.html")).await?;
+    match params.q {
+        Some(search) if !search.trim().is_empty() => Ok(Html(with_share_tags(
+            &index,
+            search.trim(),
+            &state.public_url,
+        ))),
+        _ => Ok(Html(index)),
     }
 }
 
@@ -196,10 +283,47 @@ pub async fn cancel(
     Json(payload): Json<CancelRequest>,
 ) -> Result<(), AppError> {
     tracing::debug!("{payload}");
+    audit::record_user(payload.uuid);
     state.sender.send(Message::Kill(payload.uuid)).await?;
     Ok(())
 }
 
+/// Cancel the current requests for one chart associated with the uuid in the
+/// payload, leaving the user's other charts running.
+
+pub async fn cancel_chart(
+    State(state): State<AppState>,
+    Json(payload): Json<ChartCancelRequest>,
+) -> Result<(), AppError> {
+    tracing::debug!("{payload}");
+    audit::record_user(payload.uuid);
+    state
+        .sender
+        .send(Message::KillChart((payload.uuid, payload.chart)))
+        .await?;
+    Ok(())
+}
+
+/// Cancel the current request with the request id in the payload, if it's
+/// associated with the uuid in the payload.
+
+pub async fn cancel_request(
+    State(state): State<AppState>,
+    Json(payload): Json<SingleCancelRequest>,
+) -> Result<(), AppError> {
+    tracing::debug!("{payload}");
+    audit::record_user(payload.uuid);
+    state
+        .sender
+        .send(Message::KillRequest((
+            payload.uuid,
+            payload.request_id,
+            None,
+        )))
+        .await?;
+    Ok(())
+}
+
 /// Cancel all current speech requests associated with the uuid in the payload.
 
 pub async fn cancel_speech(
@@ -207,6 +331,236 @@ pub async fn cancel_speech(
     Json(payload): Json<CancelRequest>,
 ) -> Result<(), AppError> {
     tracing::debug!("{payload}");
+    audit::record_user(payload.uuid);
     state.sender.send(Message::KillSpeech(payload.uuid)).await?;
     Ok(())
 }
+
+/// Registers a query about to run on the given connection with the reaper, which
+/// watches it until it finishes. The watch holds the connection, and the query is
+/// run on it with `Watch::run_on`. Shared queries have no client address, so they
+/// aren't counted against the limits of the client that happened to start them.
+
+async fn watch(
+    state: &AppState,
+    mut conn: Connection,
+    payload: &DataRequest,
+    chart: String,
+    speech: bool,
+    addr: Option<SocketAddr>,
+) -> Result<Watch, AppError> {
+    let query = ActiveQuery {
+        uuid: payload.uuid,
+        request_id: payload.request_id,
+        conn_id: Some(get_connection_id(&mut conn).await?),
+        speech,
+        chart,
+        ip: addr.map(|addr| addr.ip()),
+    };
+    Watch::register(&state.sender, query, state.query_timeout, Some(conn)).await
+}
+
+/// Registers a request about to wait on a shared query with the reaper. Cancelling
+/// the request only stops it waiting, and the shared query is killed once no
+/// requests are waiting on it.
+
+pub(crate) async fn wait(
+    state: &AppState,
+    payload: &DataRequest,
+    chart: String,
+    addr: SocketAddr,
+) -> Result<Watch, AppError> {
+    let query = ActiveQuery {
+        uuid: payload.uuid,
+        request_id: payload.request_id,
+        conn_id: None,
+        speech: false,
+        chart,
+        ip: Some(addr.ip()),
+    };
+    Watch::register(&state.sender, query, state.query_timeout, None).await
+}
+
+/// Returns the word count breakdown for the search term, sharing the query with
+/// identical concurrent requests. Returns the dummy data in dummy mode.
+
+pub(crate) async fn shared_breakdown(
+    state: &AppState,
+    breakdown_type: BreakdownType,
+    search: &str,
+) -> Result<Vec<BreakdownResponse>, AppError> {
+    if state.connection_pool.is_none() {
+        return Ok(dummy_get_breakdown_word_count(breakdown_type));
+    }
+    breakdown_flight(state, breakdown_type, search, 1)?
+        .result()
+        .await
+}
+
+/// Subscribes to the shared word count breakdown query for the search term,
+/// starting it if no identical request is running.
+///
+/// The shared query runs on its own connection, registered with the reaper under
+/// a random uuid and exempt from the limits of the requests waiting on it, so it's
+/// killed if it times out or every request leaves it. If it's started here, it
+/// counts the word over the given number of ranges of transcripts, one at a time,
+/// reporting the sums over the ranges counted so far after each one.
+
+pub(crate) fn breakdown_flight(
+    state: &AppState,
+    breakdown_type: BreakdownType,
+    search: &str,
+    chunks: i32,
+) -> Result<Subscription<Vec<BreakdownResponse>>, AppError> {
+    let chart = format!("breakdown/{breakdown_type}");
+    let key = flight_key(&chart, search);
+    let query = {
+        let state = state.clone();
+        let search = search.to_owned();
+        move |progress: Progress<Vec<BreakdownResponse>>| async move {
+            let pool = state
+                .connection_pool
+                .as_ref()
+                .ok_or(AppError::GenericError)?;
+            let conn = pool.get_owned().await?;
+            let payload = DataRequest::new(Uuid::new_v4(), search.clone());
+            let watch = watch(&state, conn, &payload, chart, false, None).await?;
+            watch
+                .run_on(move |conn| {
+                    Box::pin(async move {
+                        let ranges = get_transcript_ranges(conn, chunks).await?;
+                        let mut rows = vec![];
+                        for (i, range) in ranges.iter().enumerate() {
+                            let chunk = get_breakdown_word_count(
+                                conn,
+                                breakdown_type.clone(),
+                                &search,
+                                *range,
+                            )
+                            .await?;
+                            merge(&mut rows, chunk);
+                            progress.report(i + 1, ranges.len(), top(&breakdown_type, &rows));
+                        }
+                        Ok(top(&breakdown_type, &rows))
+                    })
+                })
+                .await
+        }
+    };
+    state.flights.breakdown.subscribe(key, query)
+}
+
+/// Returns the rows of a breakdown to show. Only the top speakers are shown in the
+/// speaker breakdown.
+
+fn top(breakdown_type: &BreakdownType, rows: &[BreakdownResponse]) -> Vec<BreakdownResponse> {
+    match breakdown_type {
+        BreakdownType::Speaker => top_speakers(rows),
+        _ => rows.to_vec(),
+    }
+}
+
+/// Returns the per-speaker dispersion for the search term, sharing the query with
+/// identical concurrent requests. Returns the dummy data in dummy mode, and like
+/// the database query, `BadBreakdownType` for the speaker breakdown.
+///
+/// The shared query runs on its own connection, registered with the reaper under
+/// a random uuid and exempt from the limits of the requests waiting on it, so it's
+/// killed if it times out or every request leaves it.
+
+pub(crate) async fn shared_dispersion(
+    state: &AppState,
+    breakdown_type: BreakdownType,
+    search: &str,
+) -> Result<Vec<DispersionResponse>, AppError> {
+    if state.connection_pool.is_none() {
+        return match breakdown_type {
+            BreakdownType::Speaker => Err(AppError::BadBreakdownType),
+            _ => Ok(dummy_get_breakdown_dispersion(breakdown_type)),
+        };
+    }
+    let chart = format!("dispersion/{breakdown_type}");
+    let key = flight_key(&chart, search);
+    let query = {
+        let state = state.clone();
+        let search = search.to_owned();
+        move |_| async move {
+            let pool = state
+                .connection_pool
+                .as_ref()
+                .ok_or(AppError::GenericError)?;
+            let conn = pool.get_owned().await?;
+            let payload = DataRequest::new(Uuid::new_v4(), search.clone());
+            let watch = watch(&state, conn, &payload, chart, false, None).await?;
+            watch
+                .run_on(move |conn| {
+                    Box::pin(async move {
+                        get_breakdown_dispersion(conn, breakdown_type, &search).await
+                    })
+                })
+                .await
+        }
+    };
+    state.flights.dispersion.join(key, query).await
+}
+
+/// Returns the population data for the search term, sharing the query with
+/// identical concurrent requests. Returns the dummy data in dummy mode.
+
+pub(crate) async fn shared_population(
+    state: &AppState,
+    search: &str,
+) -> Result<Vec<PopulationResponse>, AppError> {
+    if state.connection_pool.is_none() {
+        return Ok(dummy_get_population_word_count());
+    }
+    population_flight(state, search, 1)?.result().await
+}
+
+/// Subscribes to the shared population query for the search term, starting it if
+/// no identical request is running. See `breakdown_flight`.
+
+pub(crate) fn population_flight(
+    state: &AppState,
+    search: &str,
+    chunks: i32,
+) -> Result<Subscription<Vec<PopulationResponse>>, AppError> {
+    let chart = "population".to_string();
+    let key = flight_key(&chart, search);
+    let query = {
+        let state = state.clone();
+        let search = search.to_owned();
+        move |progress: Progress<Vec<PopulationResponse>>| async move {
+            let pool = state
+                .connection_pool
+                .as_ref()
+                .ok_or(AppError::GenericError)?;
+            let conn = pool.get_owned().await?;
+            let payload = DataRequest::new(Uuid::new_v4(), search.clone());
+            let watch = watch(&state, conn, &payload, chart, false, None).await?;
+            watch
+                .run_on(move |conn| {
+                    Box::pin(async move {
+                        let ranges = get_transcript_ranges(conn, chunks).await?;
+                        let mut rows = vec![];
+                        for (i, range) in ranges.iter().enumerate() {
+                            let chunk = get_population_word_count(conn, &search, *range).await?;
+                            merge(&mut rows, chunk);
+                            progress.report(i + 1, ranges.len(), rows.clone());
+                        }
+                        Ok(rows)
+                    })
+                })
+                .await
+        }
+    };
+    state.flights.population.subscribe(key, query)
+}
+
+/// Returns the normalised search term, if it can be searched for.
+
+pub(crate) fn checked_search(search: &str) -> Result<String, AppError> {
+    let search = normalise(search);
+    validate(&search)?;
+    Ok(search)
+}
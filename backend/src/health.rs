@@ -0,0 +1,79 @@
+//! Health and readiness probes, for container orchestrators.
+//!
+//! These routes are served outside the rate limit and request tracing, so that
+//! frequent probes are never refused and don't fill the logs.
+
+use crate::db::get_missing_functions;
+use crate::AppState;
+use axum::{extract::State, http::StatusCode, Json};
+use serde::Serialize;
+use tokio::time::{timeout, Duration};
+
+/// How long the readiness probe waits for a database connection before reporting
+/// the database as unavailable.
+
+const READY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The result of each readiness check.
+
+#[derive(Serialize)]
+pub struct Readiness {
+    /// Whether a connection could be checked out of the connection pool.
+    pub database: bool,
+
+    /// Whether the user-defined functions the chart queries need exist.
+    pub functions: bool,
+
+    /// Whether the reaper task is still running.
+    pub reaper: bool,
+}
+
+impl Readiness {
+    fn ready(&self) -> bool {
+        self.database && self.functions && self.reaper
+    }
+}
+
+/// Respond `200 OK` as long as the process is serving requests.
+
+pub async fn healthz() -> StatusCode {
+    StatusCode::OK
+}
+
+/// Respond `200 OK` if the backend can serve queries, or `503 Service Unavailable`
+/// if not, with the result of each check. Always ready in dummy mode, since there's
+/// no database or reaper to check.
+
+pub async fn readyz(State(state): State<AppState>) -> (StatusCode, Json<Readiness>) {
+    let readiness = match &state.connection_pool {
+        None => Readiness {
+            database: true,
+            functions: true,
+            reaper: true,
+        },
+        Some(pool) => {
+            // the reaper owns the receiving end of the channel, so it's only closed
+            // if the reaper task has stopped
+            let reaper = !state.sender.is_closed();
+            match timeout(READY_TIMEOUT, pool.get()).await {
+                Ok(Ok(mut connection)) => Readiness {
+                    database: true,
+                    functions: get_missing_functions(&mut connection).await.is_empty(),
+                    reaper,
+                },
+                _ => Readiness {
+                    database: false,
+                    functions: false,
+                    reaper,
+                },
+            }
+        }
+    };
+
+    let status = if readiness.ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(readiness))
+}
@@ -3,9 +3,22 @@
 //! Serves the frontend wasm binary, and provides api routes that return the results
 //! of SQL queries on an external MariaDB instance.
 
-use crate::db::get_connection_pool;
+use crate::admin::active_queries;
+use crate::analytics::{trending, Analytics};
+use crate::api::*;
+use crate::audit::audit;
+use crate::coalesce::Flights;
+use crate::config::{Config, Opt, Rotation};
+use crate::db::connect;
+use crate::demo::demo;
 use crate::handlers::*;
-use crate::reaper::reaper;
+use crate::health::{healthz, readyz};
+use crate::proxy::{resolve_client, TrustedProxies};
+use crate::rate_limit::{rate_limit, RateLimits};
+use crate::reaper::{reaper, ReaperConfig};
+use crate::stream::{stream_breakdown, stream_population};
+use crate::telemetry::{install_recorder, metrics, track_requests, MetricsState};
+use crate::warm::{warm, Results, WarmConfig};
 use axum::{
     routing::{get, put},
     Router,
@@ -15,43 +28,47 @@ use diesel_async::{pooled_connection::bb8::Pool, AsyncMysqlConnection};
 use std::net::{IpAddr, Ipv6Addr, SocketAddr};
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::Arc;
+use tracing_appender::rolling::RollingFileAppender;
 
 use crate::reaper::Message;
-use tokio::sync::{mpsc, mpsc::Sender};
+use crate::request_id::scope_request_id;
+use axum::middleware::{from_fn, from_fn_with_state};
+use tokio::sync::{mpsc, mpsc::Sender, oneshot};
 use tokio::time::{Duration, sleep};
+use tokio_util::sync::CancellationToken;
 use tower::ServiceBuilder;
-use tower_governor::{governor::GovernorConfigBuilder, GovernorLayer};
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
 use tower_http::services::{ServeDir, ServeFile};
 use tower_http::trace::{DefaultMakeSpan, TraceLayer};
 use tracing_subscriber::EnvFilter;
 
+mod admin;
+mod analytics;
+mod api;
+mod audit;
+mod coalesce;
+mod config;
 mod db;
+mod demo;
 mod dummy_db;
 mod error;
+mod export;
 mod handlers;
+mod health;
+mod proxy;
+mod rate_limit;
 mod reaper;
+mod render;
+mod request_id;
+mod stream;
+mod telemetry;
+mod warm;
 
-#[derive(Parser, Debug)]
-#[clap(name = "backend", about = "queens park analysis backend")]
-struct Opt {
-    #[clap(short = 'l', long = "log", default_value = "debug")]
-    log_level: String,
+/// How long handlers are given to respond after their queries are killed on
+/// shutdown.
 
-    #[clap(short = 'a', long = "addr", default_value = "::1")]
-    addr: String,
-
-    #[clap(short = 'p', long = "port", default_value = "8080")]
-    port: u16,
-
-    #[clap(long = "static-dir", default_value = "./dist")]
-    static_dir: String,
-
-    #[clap(long = "log-dir", default_value = "./logs")]
-    log_dir: String,
-
-    #[clap(short, long, default_value_t = false)]
-    dummy: bool,
-}
+const KILL_WAIT: Duration = Duration::from_secs(2);
 
 /// A struct to store the global backend state (database connection pool and mpsc
 /// channel sender).
@@ -59,112 +76,330 @@ struct Opt {
 #[derive(Clone)]
 struct AppState {
     /// The connection pool for the app. If None, we are in dummy mode and pulling
-    /// hardcoded demo data. If Some, the pool will retain 10 idle connections and
-    /// will open at max 50 connections.
+    /// hardcoded demo data.
     connection_pool: Option<Pool<AsyncMysqlConnection>>,
 
     /// A sender to send registration and kill messages to the reaper.
     sender: Sender<Message>,
+
+    /// The name of the data source we are pulling from, reported in exports.
+    data_source: String,
+
+    /// The directory the frontend is served from.
+    static_dir: PathBuf,
+
+    /// The directory the info page demos for the data source are read from.
+    demo_dir: PathBuf,
+
+    /// The public scheme and host of the app, used for absolute urls in link
+    /// previews. Empty if unset.
+    public_url: String,
+
+    /// The token required to use the admin routes. If None, they are disabled.
+    admin_token: Option<String>,
+
+    /// How long a query may run before it times out.
+    query_timeout: Duration,
+
+    /// The number of ranges of transcripts streamed chart queries are split into.
+    stream_chunks: i32,
+
+    /// How many seconds rendered chart images may be cached.
+    render_max_age: u64,
+
+    /// The shared queries in flight, so identical concurrent requests can share a
+    /// single database query.
+    flights: Flights,
+
+    /// The recent search events, for the trending searches.
+    analytics: Arc<Analytics>,
+
+    /// The precomputed results for popular search terms.
+    results: Results,
 }
 
 /// The main backend function.
 ///
-/// This function parses the command line arguments; sets up the state, the reaper,
-/// and its message channel; configures all the routes and the tracing middleware;
-/// sets up the listener and serves it via axum.
+/// This function reads the configuration from the config file, environment, and
+/// command line arguments, and either checks it and exits or sets up the state, the
+/// reaper, and its message channel; configures all the routes and the tracing
+/// middleware; sets up the listener and serves it via axum.
 
 #[tokio::main]
 async fn main() {
+    dotenvy::dotenv().ok();
     let opt = Opt::parse();
+    let config = match Config::load(&opt) {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("{error}");
+            std::process::exit(1);
+        }
+    };
+
+    if opt.check_config {
+        let ok = check_config(&config, opt.dummy).await;
+        std::process::exit(if ok { 0 } else { 1 });
+    }
+    if let Err(problems) = config.validate(opt.dummy) {
+        for problem in problems {
+            eprintln!("{problem}");
+        }
+        std::process::exit(1);
+    }
 
     if std::env::var("RUST_LOG").is_err() {
-        std::env::set_var("RUST_LOG", opt.log_level);
+        std::env::set_var("RUST_LOG", &config.log.level);
     }
 
-    let (sender, mut receiver) = mpsc::channel(50);
+    let rotation = match config.log.rotation {
+        Rotation::Minutely => tracing_appender::rolling::Rotation::MINUTELY,
+        Rotation::Hourly => tracing_appender::rolling::Rotation::HOURLY,
+        Rotation::Daily => tracing_appender::rolling::Rotation::DAILY,
+        Rotation::Never => tracing_appender::rolling::Rotation::NEVER,
+    };
+    let file_appender = RollingFileAppender::new(rotation, &config.log.dir, &config.log.file_name);
+    let (non_blocking, guard) = tracing_appender::non_blocking(file_appender);
+
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .json()
+        .with_writer(non_blocking)
+        .init();
+
+    let (sender, mut receiver) = mpsc::channel(config.queries.reaper_channel_size);
 
+    let data_source = config
+        .database
+        .data_source
+        .clone()
+        .unwrap_or_else(|| "unknown".to_owned());
     let state = AppState {
         connection_pool: if opt.dummy {
             None
         } else {
-            Some(get_connection_pool().await)
+            match connect(&config.database, &config.pool).await {
+                Ok(pool) => Some(pool),
+                Err(error) => {
+                    eprintln!("{error}");
+                    std::process::exit(1);
+                }
+            }
         },
         sender,
+        demo_dir: config.server.demo_dir.join(&data_source),
+        data_source,
+        static_dir: config.server.static_dir.clone(),
+        public_url: config.server.public_url.trim_end_matches('/').to_owned(),
+        admin_token: std::env::var("ADMIN_TOKEN")
+            .ok()
+            .filter(|token| !token.is_empty()),
+        query_timeout: Duration::from_secs(config.queries.timeout),
+        stream_chunks: config.queries.stream_chunks,
+        render_max_age: config.cache.render_max_age,
+        flights: Flights::default(),
+        analytics: Analytics::load(&config.analytics),
+        results: Results::default(),
     };
 
+    let metrics_state = config.server.metrics_port.map(|_| MetricsState {
+        handle: install_recorder(),
+        connection_pool: state.connection_pool.clone(),
+    });
+
     if !opt.dummy {
         let state = state.clone();
+        let config = ReaperConfig {
+            max_query_age: Duration::from_secs(config.queries.max_age),
+            max_queries_per_user: config.queries.max_per_user,
+            max_queries_per_ip: config.queries.max_per_ip,
+        };
         tokio::spawn(async move {
-            reaper(state.connection_pool.unwrap(), &mut receiver).await;
+            reaper(state.connection_pool.unwrap(), &mut receiver, config).await;
         });
     }
 
-    let file_appender = tracing_appender::rolling::hourly(opt.log_dir, "prefix.log");
-    let (non_blocking, _guard) = tracing_appender::non_blocking(file_appender);
-
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env())
-        .json()
-        .with_writer(non_blocking)
-        .init();
-
-    let governor_conf = std::sync::Arc::new(
-        GovernorConfigBuilder::default()
-            .per_second(1)
-            .burst_size(10)
-            .finish()
-            .expect("couldn't build rate limit governor"),
-    );
-    let governor_limiter = governor_conf.limiter().clone();
+    let rate_limits = RateLimits::new(&config.rate_limit);
+    let trusted_proxies = Arc::new(TrustedProxies {
+        ranges: config.rate_limit.trusted_proxies.clone(),
+        header: config.rate_limit.forwarded_header,
+    });
 
     let service = ServiceBuilder::new()
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+        .layer(PropagateRequestIdLayer::x_request_id())
         .layer(
             TraceLayer::new_for_http().make_span_with(DefaultMakeSpan::new().include_headers(true)),
         )
-        .layer(GovernorLayer {
-            config: governor_conf,
-        });
+        .layer(from_fn(scope_request_id))
+        .layer(from_fn_with_state(trusted_proxies, resolve_client))
+        .layer(from_fn_with_state(rate_limits, rate_limit));
 
-    tokio::spawn(async move {
-        loop {
-            sleep(Duration::from_secs(60)).await;
-            tracing::trace!("rate limiting storage size: {}", governor_limiter.len());
-            governor_limiter.retain_recent();
-        }
-    });
+    let reaper_sender = state.sender.clone();
+    let warm_state = state.clone();
+    let probes = Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .with_state(state.clone());
 
-    let index_path = PathBuf::from(&opt.static_dir).join("index.html");
+    let index_path = config.server.static_dir.join("index.html");
     let app = Router::new()
         .route("/api/speakers", get(speakers))
         .route("/api/breakdown/{type}", put(breakdown))
+        .route("/api/dispersion/{type}", put(dispersion))
         .route("/api/population", put(population))
+        .route("/api/stream/breakdown/{type}", put(stream_breakdown))
+        .route("/api/stream/population", put(stream_population))
         .route("/api/speeches/{breakdown}/{id}", put(speeches))
         .route("/api/cancel", put(cancel))
         .route("/api/cancel/speeches", put(cancel_speech))
+        .route("/api/cancel/chart", put(cancel_chart))
+        .route("/api/cancel/request", put(cancel_request))
+        .route("/api/render/{file}", get(render))
+        .route("/search", get(search_page))
+        .route("/api/v1/openapi.json", get(openapi))
+        .route("/api/v1/speakers", get(speakers_v1))
+        .route("/api/v1/breakdown/{type}", get(breakdown_v1))
+        .route("/api/v1/dispersion/{type}", get(dispersion_v1))
+        .route("/api/v1/population", get(population_v1))
+        .route("/api/v1/speeches/{breakdown}/{id}", get(speeches_v1))
+        .route("/api/admin/queries", get(active_queries))
+        .route("/api/trending", get(trending))
+        .route("/api/demo/{name}", get(demo))
+        .route_layer(from_fn_with_state(state.clone(), audit))
+        .route_layer(from_fn(track_requests))
         .with_state(state)
         .fallback_service(
-            ServeDir::new(&opt.static_dir).not_found_service(ServeFile::new(index_path)),
+            ServeDir::new(&config.server.static_dir).not_found_service(ServeFile::new(index_path)),
         )
         .layer(service)
+        .merge(probes)
         .into_make_service_with_connect_info::<SocketAddr>();
 
-    let mut port = opt.port;
-    if let Ok(port_env) = std::env::var("PORT") {
-        port = port_env
-            .parse::<u16>()
-            .expect("couldn't parse port into u16");
-    }
+    let ip = IpAddr::from_str(&config.server.addr).unwrap_or(IpAddr::V6(Ipv6Addr::LOCALHOST));
+    let sock_addr = SocketAddr::from((ip, config.server.port));
 
-    let sock_addr = SocketAddr::from((
-        IpAddr::from_str(opt.addr.as_str()).unwrap_or(IpAddr::V6(Ipv6Addr::LOCALHOST)),
-        port,
-    ));
+    if let (Some(metrics_state), Some(metrics_port)) = (metrics_state, config.server.metrics_port) {
+        let metrics_app = Router::new()
+            .route("/metrics", get(metrics))
+            .with_state(metrics_state);
+        let metrics_ip = IpAddr::from_str(&config.server.metrics_addr)
+            .unwrap_or(IpAddr::V6(Ipv6Addr::LOCALHOST));
+        let metrics_addr = SocketAddr::from((metrics_ip, metrics_port));
+        let metrics_listener = tokio::net::TcpListener::bind(&metrics_addr)
+            .await
+            .expect("unable to bind metrics listener");
+        tokio::spawn(async move {
+            axum::serve(metrics_listener, metrics_app)
+                .await
+                .expect("Unable to start metrics server");
+        });
+    }
 
     let listener = tokio::net::TcpListener::bind(&sock_addr)
         .await
         .expect("unable to bind listener");
 
-    axum::serve(listener, app)
-        .await
-        .expect("Unable to start server");
+    let shutdown = CancellationToken::new();
+    tokio::spawn({
+        let shutdown = shutdown.clone();
+        async move {
+            shutdown_signal().await;
+            tracing::info!("shutting down");
+            shutdown.cancel();
+        }
+    });
+
+    if !opt.dummy && config.cache.warm_interval > 0 {
+        let config = WarmConfig {
+            terms: config.cache.warm_terms.clone(),
+            top: config.cache.warm_top,
+            interval: Duration::from_secs(config.cache.warm_interval),
+        };
+        tokio::spawn(warm(warm_state, config, shutdown.clone()));
+    }
+
+    let server =
+        axum::serve(listener, app).with_graceful_shutdown(shutdown.clone().cancelled_owned());
+    let grace = Duration::from_secs(config.server.shutdown_grace);
+    tokio::select! {
+        result = server => result.expect("Unable to start server"),
+        _ = drain(shutdown, reaper_sender, grace, opt.dummy) => {
+            tracing::warn!("shut down with requests still running");
+        }
+    }
+
+    // flush the logs, since the writer's worker thread isn't joined on exit
+    drop(guard);
+}
+
+/// Resolves when the process receives SIGINT or SIGTERM.
+
+async fn shutdown_signal() {
+    let interrupt = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("couldn't listen for SIGINT");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("couldn't listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => {},
+        _ = terminate => {},
+    }
+}
+
+/// Waits for shutdown to start, then gives running requests the grace period to
+/// finish before asking the reaper to kill every query still running, and gives the
+/// cancelled handlers `KILL_WAIT` to respond. Resolves when the server should stop
+/// waiting for requests.
+
+async fn drain(shutdown: CancellationToken, sender: Sender<Message>, grace: Duration, dummy: bool) {
+    shutdown.cancelled().await;
+    sleep(grace).await;
+
+    if !dummy {
+        tracing::info!("killing queries still running after {grace:?}");
+        let (reply, killed) = oneshot::channel();
+        if sender.send(Message::KillAll(reply)).await.is_ok() {
+            let _ = killed.await;
+        }
+    }
+    sleep(KILL_WAIT).await;
+}
+
+/// Checks the configuration, printing every problem found, and then tests the
+/// database connection unless in dummy mode. Returns whether everything is fine.
+
+async fn check_config(config: &Config, dummy: bool) -> bool {
+    if let Err(problems) = config.validate(dummy) {
+        for problem in problems {
+            println!("invalid: {problem}");
+        }
+        return false;
+    }
+    println!("configuration is valid");
+    if dummy {
+        return true;
+    }
+
+    match connect(&config.database, &config.pool).await {
+        Ok(_) => {
+            println!("connected to the database");
+            true
+        }
+        Err(error) => {
+            println!("{error}");
+            false
+        }
+    }
 }
@@ -0,0 +1,347 @@
+//! Client addresses behind trusted reverse proxies.
+//!
+//! When the backend runs behind a reverse proxy, every connection comes from the
+//! proxy, and the client's address is only known from the `Forwarded` or
+//! `X-Forwarded-For` header the proxy adds. The headers can be forged by clients,
+//! so they're only believed for connections from the configured trusted proxies,
+//! and only the one header those proxies are configured to write is read. Most
+//! proxies pass the other header through from the client untouched.
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::HeaderMap,
+    middleware::Next,
+    response::Response,
+};
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::fmt;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// The header trusted proxies add the addresses they forwarded requests for to.
+
+#[derive(Deserialize, ValueEnum, Clone, Copy, PartialEq, Default, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum ForwardedHeader {
+    /// The standard `Forwarded` header, defined in RFC 7239.
+    Forwarded,
+    /// The `X-Forwarded-For` header, which most proxies write by default.
+    #[default]
+    XForwardedFor,
+}
+
+impl ForwardedHeader {
+    fn name(&self) -> &'static str {
+        match self {
+            ForwardedHeader::Forwarded => "forwarded",
+            ForwardedHeader::XForwardedFor => "x-forwarded-for",
+        }
+    }
+}
+
+/// The reverse proxies whose forwarded header is believed, and which header they
+/// write.
+
+#[derive(Clone, Default, Debug)]
+pub struct TrustedProxies {
+    pub ranges: Vec<IpRange>,
+    pub header: ForwardedHeader,
+}
+
+/// A range of ip addresses, written as a single address, e.g. `10.0.0.1`, or in
+/// CIDR notation, e.g. `10.0.0.0/8`.
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(try_from = "String")]
+pub struct IpRange {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpRange {
+    /// Returns whether the range contains the address. IPv4 addresses mapped to
+    /// IPv6, as seen on dual-stack listeners, are compared as IPv4 addresses.
+
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        match (self.addr, addr.to_canonical()) {
+            (IpAddr::V4(range), IpAddr::V4(addr)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(range) & mask == u32::from(addr) & mask
+            }
+            (IpAddr::V6(range), IpAddr::V6(addr)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(range) & mask == u128::from(addr) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// An error parsing an ip range.
+
+#[derive(Debug)]
+pub struct IpRangeParseError(String);
+
+impl fmt::Display for IpRangeParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "'{}' isn't an ip address or CIDR range", self.0)
+    }
+}
+
+impl std::error::Error for IpRangeParseError {}
+
+impl FromStr for IpRange {
+    type Err = IpRangeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || IpRangeParseError(s.to_owned());
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let addr = IpAddr::from_str(addr.trim())
+            .map_err(|_| error())?
+            .to_canonical();
+        let max_prefix = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix.trim().parse::<u8>().map_err(|_| error())?,
+            None => max_prefix,
+        };
+        if prefix > max_prefix {
+            return Err(error());
+        }
+        Ok(IpRange { addr, prefix })
+    }
+}
+
+impl TryFrom<String> for IpRange {
+    type Error = IpRangeParseError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        IpRange::from_str(&s)
+    }
+}
+
+/// Returns the address of the client that sent a request over a connection from
+/// the given peer.
+///
+/// If the peer is a trusted proxy, the addresses the request was forwarded for are
+/// read from the right of the proxies' header, skipping trusted proxies, and the
+/// first address not trusted is the client's. Otherwise the peer is the client. The
+/// other forwarded header is never read.
+
+pub fn client_ip(peer: IpAddr, headers: &HeaderMap, proxies: &TrustedProxies) -> IpAddr {
+    let trusted = |addr: IpAddr| proxies.ranges.iter().any(|range| range.contains(addr));
+    if !trusted(peer) {
+        return peer;
+    }
+
+    let mut client = peer;
+    for hop in forwarded_for(headers, proxies.header).into_iter().rev() {
+        match hop {
+            Some(addr) => {
+                client = addr;
+                if !trusted(addr) {
+                    break;
+                }
+            }
+            // everything further left was written by someone we can't trust
+            None => break,
+        }
+    }
+    client
+}
+
+/// Returns the addresses the request was forwarded for in the given header, from
+/// the client to the last proxy, with None for any that are hidden or can't be
+/// parsed.
+
+fn forwarded_for(headers: &HeaderMap, header: ForwardedHeader) -> Vec<Option<IpAddr>> {
+    let hops = headers
+        .get_all(header.name())
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','));
+    match header {
+        ForwardedHeader::Forwarded => hops.map(forwarded_element_for).collect(),
+        ForwardedHeader::XForwardedFor => {
+            hops.map(|hop| IpAddr::from_str(hop.trim()).ok()).collect()
+        }
+    }
+}
+
+/// Returns the address in the `for` parameter of one element of a `Forwarded`
+/// header, as defined in RFC 7239, e.g. `for=192.0.2.60;proto=http` or
+/// `for="[2001:db8::1]:4711"`. The address may have a port, and IPv6 addresses are
+/// in brackets.
+
+fn forwarded_element_for(element: &str) -> Option<IpAddr> {
+    let node = element.split(';').find_map(|pair| {
+        let (key, value) = pair.split_once('=')?;
+        key.trim()
+            .eq_ignore_ascii_case("for")
+            .then(|| value.trim().trim_matches('"'))
+    })?;
+
+    if let Some(bracketed) = node.strip_prefix('[') {
+        let (addr, _) = bracketed.split_once(']')?;
+        return Ipv6Addr::from_str(addr).ok().map(IpAddr::V6);
+    }
+    let addr = match node.split_once(':') {
+        Some((addr, port)) if !port.contains(':') => addr,
+        _ => node,
+    };
+    IpAddr::from_str(addr).ok()
+}
+
+/// Middleware that replaces the connection info of requests from trusted proxies
+/// with the address of the client they were forwarded for, so that rate limits,
+/// query limits, and logs apply to the client rather than the proxy.
+
+pub async fn resolve_client(
+    State(trusted_proxies): State<Arc<TrustedProxies>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let client = client_ip(peer.ip(), request.headers(), &trusted_proxies);
+    if client != peer.ip() {
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::new(client, peer.port())));
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn range(s: &str) -> IpRange {
+        IpRange::from_str(s).unwrap()
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        IpAddr::from_str(s).unwrap()
+    }
+
+    fn header_map(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    fn trusting(header: ForwardedHeader) -> TrustedProxies {
+        TrustedProxies {
+            ranges: vec![range("10.0.0.0/8")],
+            header,
+        }
+    }
+
+    #[test]
+    fn range_contains() {
+        assert!(range("10.0.0.0/8").contains(ip("10.1.2.3")));
+        assert!(!range("10.0.0.0/8").contains(ip("11.0.0.1")));
+        assert!(range("192.168.1.1").contains(ip("192.168.1.1")));
+        assert!(!range("192.168.1.1").contains(ip("192.168.1.2")));
+        assert!(range("0.0.0.0/0").contains(ip("8.8.8.8")));
+        assert!(range("2001:db8::/32").contains(ip("2001:db8:1::1")));
+        assert!(!range("2001:db8::/32").contains(ip("2001:db9::1")));
+        assert!(range("10.0.0.0/8").contains(ip("::ffff:10.0.0.1")));
+        assert!(!range("10.0.0.0/8").contains(ip("2001:db8::1")));
+    }
+
+    #[test]
+    fn range_parse_errors() {
+        assert!(IpRange::from_str("10.0.0.0/33").is_err());
+        assert!(IpRange::from_str("::/129").is_err());
+        assert!(IpRange::from_str("not an ip").is_err());
+        assert!(IpRange::from_str("10.0.0.0/x").is_err());
+    }
+
+    #[test]
+    fn forwarded_elements() {
+        assert_eq!(
+            forwarded_element_for("for=192.0.2.60;proto=http"),
+            Some(ip("192.0.2.60"))
+        );
+        assert_eq!(
+            forwarded_element_for(" For=\"192.0.2.60:4711\""),
+            Some(ip("192.0.2.60"))
+        );
+        assert_eq!(
+            forwarded_element_for("for=\"[2001:db8::1]:4711\""),
+            Some(ip("2001:db8::1"))
+        );
+        assert_eq!(
+            forwarded_element_for("proto=https;for=[2001:db8::1]"),
+            Some(ip("2001:db8::1"))
+        );
+        assert_eq!(forwarded_element_for("for=unknown"), None);
+        assert_eq!(forwarded_element_for("for=_hidden"), None);
+        assert_eq!(forwarded_element_for("proto=http"), None);
+    }
+
+    #[test]
+    fn untrusted_peer_is_the_client() {
+        let headers = header_map(&[("x-forwarded-for", "1.2.3.4")]);
+        let proxies = trusting(ForwardedHeader::XForwardedFor);
+        assert_eq!(client_ip(ip("8.8.8.8"), &headers, &proxies), ip("8.8.8.8"));
+    }
+
+    #[test]
+    fn trusted_proxy_gives_the_client() {
+        let headers = header_map(&[("x-forwarded-for", "1.2.3.4, 10.0.0.2")]);
+        let proxies = trusting(ForwardedHeader::XForwardedFor);
+        assert_eq!(client_ip(ip("10.0.0.1"), &headers, &proxies), ip("1.2.3.4"));
+
+        let headers = header_map(&[("forwarded", "for=1.2.3.4, for=10.0.0.2")]);
+        let proxies = trusting(ForwardedHeader::Forwarded);
+        assert_eq!(client_ip(ip("10.0.0.1"), &headers, &proxies), ip("1.2.3.4"));
+    }
+
+    #[test]
+    fn spoofed_addresses_left_of_the_client_are_ignored() {
+        let headers = header_map(&[("x-forwarded-for", "6.6.6.6, 1.2.3.4")]);
+        let proxies = trusting(ForwardedHeader::XForwardedFor);
+        assert_eq!(client_ip(ip("10.0.0.1"), &headers, &proxies), ip("1.2.3.4"));
+    }
+
+    #[test]
+    fn the_other_header_is_never_read() {
+        // a client sends its own Forwarded header through a proxy that only appends
+        // to X-Forwarded-For
+        let spoofed = header_map(&[("forwarded", "for=6.6.6.6"), ("x-forwarded-for", "1.2.3.4")]);
+        let proxies = trusting(ForwardedHeader::XForwardedFor);
+        assert_eq!(client_ip(ip("10.0.0.1"), &spoofed, &proxies), ip("1.2.3.4"));
+
+        // and the proxy's header is missing, so there's no fallback to the spoofed one
+        let spoofed = header_map(&[("forwarded", "for=6.6.6.6")]);
+        assert_eq!(
+            client_ip(ip("10.0.0.1"), &spoofed, &proxies),
+            ip("10.0.0.1")
+        );
+
+        let spoofed = header_map(&[("x-forwarded-for", "6.6.6.6")]);
+        let proxies = trusting(ForwardedHeader::Forwarded);
+        assert_eq!(
+            client_ip(ip("10.0.0.1"), &spoofed, &proxies),
+            ip("10.0.0.1")
+        );
+    }
+
+    #[test]
+    fn unparseable_hop_stops_the_search() {
+        let headers = header_map(&[("x-forwarded-for", "1.2.3.4, garbage, 10.0.0.2")]);
+        let proxies = trusting(ForwardedHeader::XForwardedFor);
+        assert_eq!(
+            client_ip(ip("10.0.0.1"), &headers, &proxies),
+            ip("10.0.0.2")
+        );
+    }
+}
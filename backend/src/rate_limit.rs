@@ -0,0 +1,187 @@
+//! Per-route and per-client rate limits.
+//!
+//! Requests are limited separately for each group of routes, so that loading the
+//! page's static files and metadata doesn't use up the quota for chart queries.
+//! Clients are keyed by ip address, or by api key if they send one of the
+//! configured keys in the `X-Api-Key` header, in which case every route shares the
+//! api key quota instead.
+
+use crate::config::{Policy, RateLimitConfig};
+use crate::error::AppError;
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use governor::{clock::Clock, clock::DefaultClock, DefaultKeyedRateLimiter, Quota};
+use metrics::counter;
+use std::collections::HashSet;
+use std::net::{IpAddr, SocketAddr};
+use std::num::NonZeroU32;
+use std::sync::Arc;
+use tokio::time::{sleep, Duration};
+
+/// The header clients send their api key in.
+
+const API_KEY_HEADER: &str = "x-api-key";
+
+/// How often the limiters forget clients whose quota has fully replenished.
+
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A group of routes sharing a rate limit.
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RouteGroup {
+    /// The frontend's files, and any other route outside the api.
+    StaticFiles,
+    /// Cheap api routes: speakers, cancellations, the api description, and admin.
+    Metadata,
+    /// Chart data, which runs word counts over the whole corpus.
+    Queries,
+    /// Speeches matching a search term.
+    Speeches,
+}
+
+impl RouteGroup {
+    /// Returns the group a request path belongs to.
+
+    pub fn of(path: &str) -> RouteGroup {
+        let Some(route) = path
+            .strip_prefix("/api/v1/")
+            .or_else(|| path.strip_prefix("/api/"))
+        else {
+            return RouteGroup::StaticFiles;
+        };
+        match route.split('/').next().unwrap_or_default() {
+            "breakdown" | "dispersion" | "population" | "stream" | "render" => RouteGroup::Queries,
+            "speeches" => RouteGroup::Speeches,
+            _ => RouteGroup::Metadata,
+        }
+    }
+
+    /// Returns the name of the group, as used in metric labels.
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RouteGroup::StaticFiles => "static_files",
+            RouteGroup::Metadata => "metadata",
+            RouteGroup::Queries => "queries",
+            RouteGroup::Speeches => "speeches",
+        }
+    }
+}
+
+/// The rate limiters for each route group, and for api keys.
+
+pub struct RateLimits {
+    static_files: DefaultKeyedRateLimiter<IpAddr>,
+    metadata: DefaultKeyedRateLimiter<IpAddr>,
+    queries: DefaultKeyedRateLimiter<IpAddr>,
+    speeches: DefaultKeyedRateLimiter<IpAddr>,
+    api_key: DefaultKeyedRateLimiter<String>,
+    api_keys: HashSet<String>,
+}
+
+impl RateLimits {
+    /// Builds the rate limiters from the configured policies, and starts a task
+    /// that periodically forgets idle clients.
+
+    pub fn new(config: &RateLimitConfig) -> Arc<RateLimits> {
+        let limits = Arc::new(RateLimits {
+            static_files: DefaultKeyedRateLimiter::keyed(quota(&config.static_files)),
+            metadata: DefaultKeyedRateLimiter::keyed(quota(&config.metadata)),
+            queries: DefaultKeyedRateLimiter::keyed(quota(&config.queries)),
+            speeches: DefaultKeyedRateLimiter::keyed(quota(&config.speeches)),
+            api_key: DefaultKeyedRateLimiter::keyed(quota(&config.api_key)),
+            api_keys: config.api_keys.iter().cloned().collect(),
+        });
+
+        let cleanup = limits.clone();
+        tokio::spawn(async move {
+            loop {
+                sleep(CLEANUP_INTERVAL).await;
+                for limiter in cleanup.ip_limiters() {
+                    limiter.retain_recent();
+                }
+                cleanup.api_key.retain_recent();
+                let clients = cleanup
+                    .ip_limiters()
+                    .map(|limiter| limiter.len())
+                    .sum::<usize>();
+                tracing::trace!("rate limiting storage size: {clients}");
+            }
+        });
+        limits
+    }
+
+    fn ip_limiters(&self) -> impl Iterator<Item = &DefaultKeyedRateLimiter<IpAddr>> {
+        [
+            &self.static_files,
+            &self.metadata,
+            &self.queries,
+            &self.speeches,
+        ]
+        .into_iter()
+    }
+
+    fn limiter(&self, group: RouteGroup) -> &DefaultKeyedRateLimiter<IpAddr> {
+        match group {
+            RouteGroup::StaticFiles => &self.static_files,
+            RouteGroup::Metadata => &self.metadata,
+            RouteGroup::Queries => &self.queries,
+            RouteGroup::Speeches => &self.speeches,
+        }
+    }
+
+    /// Takes one request from the client's quota for the route group. Returns
+    /// `RateLimited`, with the number of seconds until the client may try again, if
+    /// the quota is used up, and `BadApiKey` if the client sent an unknown api key.
+
+    fn check(&self, group: RouteGroup, ip: IpAddr, api_key: Option<&str>) -> Result<(), AppError> {
+        let checked = match api_key {
+            Some(key) if self.api_keys.contains(key) => self.api_key.check_key(&key.to_owned()),
+            Some(_) => return Err(AppError::BadApiKey),
+            None => self.limiter(group).check_key(&ip),
+        };
+        checked.map_err(|not_until| {
+            let wait = not_until.wait_time_from(DefaultClock::default().now());
+            AppError::RateLimited(wait.as_millis().div_ceil(1000) as u64)
+        })
+    }
+}
+
+/// Returns the governor quota for a policy. Policies are validated when the config
+/// is loaded, so the period and burst are never zero.
+
+fn quota(policy: &Policy) -> Quota {
+    Quota::with_period(Duration::from_millis(policy.replenish_ms))
+        .expect("rate limit period must be positive")
+        .allow_burst(NonZeroU32::new(policy.burst).expect("rate limit burst must be positive"))
+}
+
+/// Middleware that applies the rate limit of the request's route group to its
+/// client, responding `429 Too Many Requests` if the client's quota is used up.
+
+pub async fn rate_limit(
+    State(limits): State<Arc<RateLimits>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let group = RouteGroup::of(request.uri().path());
+    let api_key = request
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|key| key.to_str().ok());
+
+    match limits.check(group, addr.ip(), api_key) {
+        Ok(()) => next.run(request).await,
+        Err(error) => {
+            if let AppError::RateLimited(_) = error {
+                counter!("rate_limited_requests_total", "group" => group.as_str()).increment(1);
+            }
+            error.into_response()
+        }
+    }
+}
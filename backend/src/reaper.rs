@@ -1,10 +1,30 @@
 use crate::db::kill_connection_id;
-use diesel_async::{pooled_connection::bb8::Pool, AsyncMysqlConnection};
+use crate::error::AppError;
+use diesel_async::pooled_connection::bb8::{Pool, PooledConnection};
+use diesel_async::AsyncMysqlConnection;
+use futures::future::BoxFuture;
+use metrics::{counter, gauge};
+use serde::Serialize;
 use std::collections::HashMap;
-use tokio::sync::mpsc::Receiver;
+use std::future::Future;
+use std::net::IpAddr;
+use tokio::sync::{
+    mpsc::{error::TrySendError, Receiver, Sender},
+    oneshot,
+};
+use tokio::time::{interval, sleep, Duration, Instant};
 use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
+/// How often the reaper sweeps its store for expired queries and users with no
+/// active queries.
+
+const SWEEP_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A pooled connection a handler runs a watched query on.
+
+pub type Connection = PooledConnection<'static, AsyncMysqlConnection>;
+
 /// A struct representing an active query that can be passed in registration and
 /// deregistration messages.
 
@@ -13,24 +33,84 @@ pub struct ActiveQuery {
     /// A unique user id, sent by the frontend.
     pub uuid: Uuid,
 
-    /// An active MariaDB connection id, able to be killed.
-    pub conn_id: i32,
+    /// A unique id for the data request running this query, sent by the frontend.
+    pub request_id: Uuid,
+
+    /// An active MariaDB connection id, able to be killed. None if the request is
+    /// waiting on a query shared with other requests, which is registered
+    /// separately.
+    pub conn_id: Option<i32>,
 
     /// Whether this query is a speech query or not.
     pub speech: bool,
+
+    /// The chart this query is for, named by its api path, e.g. `breakdown/party`.
+    /// Speech queries are named `speeches`.
+    pub chart: String,
+
+    /// The ip address of the client that started the query. None for a query
+    /// shared with other requests, which isn't counted against any client's limits,
+    /// since each request waiting on it is registered and limited separately.
+    pub ip: Option<IpAddr>,
+}
+
+/// The limits the reaper enforces on active queries.
+
+#[derive(Clone, Copy)]
+pub struct ReaperConfig {
+    /// How long a query may be registered before the reaper kills it, regardless
+    /// of its handler. This catches queries whose handlers failed to deregister.
+    pub max_query_age: Duration,
+
+    /// The most queries a single user may have running at once.
+    pub max_queries_per_user: usize,
+
+    /// The most queries clients at a single ip address may have running at once.
+    pub max_queries_per_ip: usize,
+}
+
+/// An active query in the reaper's store, with the token that cancels its handler
+/// and the time it was registered.
+
+struct RegisteredQuery {
+    query: ActiveQuery,
+    token: CancellationToken,
+    started: Instant,
+}
+
+/// A summary of one active query, as listed by the admin endpoint.
+
+#[derive(Serialize)]
+pub struct QuerySummary {
+    pub request_id: Uuid,
+    pub conn_id: Option<i32>,
+    /// Either `speech` or `chart`.
+    pub kind: &'static str,
+    pub chart: String,
+    /// How long the query has been running, in seconds.
+    pub age: f64,
+}
+
+/// The active queries of one user, as listed by the admin endpoint.
+
+#[derive(Serialize)]
+pub struct UserQueries {
+    pub uuid: Uuid,
+    pub queries: Vec<QuerySummary>,
 }
 
 /// A message that can be sent by handlers and recieved by the reaper through a
 /// tokio multi-producer single-consumer channel.
 
-#[derive(Clone)]
 pub enum Message {
-    /// A registration message, including the query information and a cancellation
-    /// token that can directly cancel the handler.
+    /// A registration message, including the query information, a cancellation
+    /// token that can directly cancel the handler, and a oneshot sender the reaper
+    /// replies on with whether the query may run.
     ///
     /// This message means the handler is running an active query that should be
-    /// killed if necessary.
-    Register((ActiveQuery, CancellationToken)),
+    /// killed if necessary. The reaper refuses the query if its user or ip address
+    /// already has too many queries running.
+    Register((ActiveQuery, CancellationToken, oneshot::Sender<bool>)),
 
     /// A deregistration message, including the query information for reference.
     ///
@@ -51,6 +131,41 @@ pub enum Message {
     /// other ongoing chart queries that are still running in the main interface
     /// page.
     KillSpeech(Uuid),
+
+    /// A kill chart queries message, telling the reaper to kill the queries for one
+    /// chart started by a particular user, like `KillRequest` for every request
+    /// the chart has running.
+    ///
+    /// This message is sent by clients that don't track their request ids, so
+    /// that a chart that's hidden or destroyed while loading doesn't keep its query
+    /// running, while the user's other charts are left alone.
+    KillChart((Uuid, String)),
+
+    /// A kill request message, telling the reaper to kill the query for a single
+    /// data request started by a particular user.
+    ///
+    /// This message is sent when a chart starts a new request while its previous
+    /// one is still running, e.g. after a new search, so that only the stale
+    /// query is killed and the user's fresh queries are left alone.
+    ///
+    /// Handlers that give up on a query running on their own connection, because it
+    /// timed out, was cancelled, or the handler was dropped, send this message with
+    /// the connection. The reaper only drops the connection, returning it to the
+    /// pool, once the query has been killed, so that the kill can't land on another
+    /// query run on the same connection afterwards.
+    KillRequest((Uuid, Uuid, Option<Connection>)),
+
+    /// A list message, asking the reaper to send a summary of every active query,
+    /// grouped by user, back through the oneshot channel.
+    List(oneshot::Sender<Vec<UserQueries>>),
+
+    /// A kill all message, telling the reaper to kill every active query and refuse
+    /// any registered after it, replying through the oneshot channel once the
+    /// queries are killed.
+    ///
+    /// This message is sent when the server shuts down, so that queries whose
+    /// handlers outlive the grace period don't keep running on the database.
+    KillAll(oneshot::Sender<()>),
 }
 
 /// An async reaper for the backend that kills database queries when requested.
@@ -60,68 +175,326 @@ pub enum Message {
 /// on receiving a deregistration message these details are dropped. When a kill
 /// message is received, the active queries associated with that user are killed
 /// and their details dropped from the store.
+///
+/// Each user's queries are keyed by request id. Killing a query cancels its handler
+/// and, if it has a connection of its own, kills the query on MariaDB. Requests
+/// waiting on a shared query are only cancelled, since the shared query is killed
+/// by its own registration once no requests are waiting on it. Every `SWEEP_INTERVAL` the reaper kills queries that have been
+/// running for longer than the maximum query age, and forgets users with no
+/// active queries. Once told to kill all queries on shutdown, the reaper refuses
+/// every new query.
 
-pub async fn reaper(pool: Pool<AsyncMysqlConnection>, receiver: &mut Receiver<Message>) {
-    let mut active_queries: HashMap<Uuid, HashMap<(i32, bool), CancellationToken>> = HashMap::new();
+pub async fn reaper(
+    pool: Pool<AsyncMysqlConnection>,
+    receiver: &mut Receiver<Message>,
+    config: ReaperConfig,
+) {
+    let max_query_age = config.max_query_age;
+    let mut active_queries: HashMap<Uuid, HashMap<Uuid, RegisteredQuery>> = HashMap::new();
+    let mut sweep = interval(SWEEP_INTERVAL);
+    let mut shutting_down = false;
+
+    loop {
+        let recv = tokio::select! {
+            recv = receiver.recv() => match recv {
+                Some(recv) => recv,
+                None => break,
+            },
+            _ = sweep.tick() => {
+                let mut expired = 0;
+                for request_map in active_queries.values_mut() {
+                    expired += kill_where(&pool, request_map, |rq| {
+                        rq.started.elapsed() > max_query_age
+                    })
+                    .await;
+                }
+                if expired > 0 {
+                    tracing::warn!("reaper killed {expired} queries older than {max_query_age:?}");
+                }
+                count_kills("expired", expired);
+                active_queries.retain(|_, request_map| !request_map.is_empty());
+                continue;
+            }
+        };
 
-    while let Some(recv) = receiver.recv().await {
         match recv {
-            Message::Register((aq, cancel_token)) => {
-                if let Some(conn_id_map) = active_queries.get_mut(&aq.uuid) {
-                    conn_id_map.insert((aq.conn_id, aq.speech), cancel_token);
+            Message::Register((aq, cancel_token, reply)) => {
+                let over_limit = aq.ip.is_some_and(|ip| {
+                    let user_queries = active_queries.get(&aq.uuid).map_or(0, |m| m.len());
+                    let ip_queries = active_queries
+                        .values()
+                        .flat_map(|request_map| request_map.values())
+                        .filter(|rq| rq.query.ip == Some(ip))
+                        .count();
+                    user_queries >= config.max_queries_per_user
+                        || ip_queries >= config.max_queries_per_ip
+                });
+
+                if shutting_down {
+                    let _ = reply.send(false);
+                } else if over_limit {
+                    tracing::warn!("reaper refused a query from {} ({:?})", aq.uuid, aq.ip);
+                    counter!("reaper_refused_queries_total").increment(1);
+                    let _ = reply.send(false);
                 } else {
-                    let mut conn_id_map = HashMap::new();
-                    conn_id_map.insert((aq.conn_id, aq.speech), cancel_token);
-                    active_queries.insert(aq.uuid, conn_id_map);
+                    active_queries.entry(aq.uuid).or_default().insert(
+                        aq.request_id,
+                        RegisteredQuery {
+                            query: aq,
+                            token: cancel_token,
+                            started: Instant::now(),
+                        },
+                    );
+                    let _ = reply.send(true);
                 }
             }
             Message::Deregister(aq) => {
-                if let Some(conn_id_map) = active_queries.get_mut(&aq.uuid) {
-                    conn_id_map.remove(&(aq.conn_id, aq.speech));
+                if let Some(request_map) = active_queries.get_mut(&aq.uuid) {
+                    request_map.remove(&aq.request_id);
                 }
             }
             Message::Kill(uuid) => {
-                if let Some(conn_id_map) = active_queries.get_mut(&uuid) {
-                    // cancel all tokens *before* killing active queries so the route handlers
-                    // return the proper 204 code indicating a cancel, instead of an error
-                    let conn_ids = conn_id_map
-                        .iter()
-                        .map(|((conn_id, _), cancel_token)| {
-                            cancel_token.cancel();
-                            conn_id
-                        })
-                        .collect::<Vec<&i32>>();
-
-                    if let Ok(mut conn) = pool.get().await {
-                        for conn_id in conn_ids {
-                            let _ = kill_connection_id(&mut conn, conn_id).await;
-                        }
-                    }
-                    conn_id_map.clear();
+                if let Some(request_map) = active_queries.get_mut(&uuid) {
+                    count_kills("user", kill_where(&pool, request_map, |_| true).await);
                 }
             }
             Message::KillSpeech(uuid) => {
-                if let Some(conn_id_map) = active_queries.get_mut(&uuid) {
-                    let conn_ids = conn_id_map
-                        .iter()
-                        .filter_map(|((conn_id, speech), cancel_token)| {
-                            if *speech {
-                                cancel_token.cancel();
-                                Some(conn_id)
-                            } else {
-                                None
-                            }
-                        })
-                        .collect::<Vec<&i32>>();
-
-                    if let Ok(mut conn) = pool.get().await {
-                        for conn_id in conn_ids {
-                            let _ = kill_connection_id(&mut conn, conn_id).await;
-                        }
-                    }
-                    conn_id_map.clear();
+                if let Some(request_map) = active_queries.get_mut(&uuid) {
+                    let killed = kill_where(&pool, request_map, |rq| rq.query.speech).await;
+                    count_kills("speech", killed);
                 }
             }
+            Message::KillChart((uuid, chart)) => {
+                if let Some(request_map) = active_queries.get_mut(&uuid) {
+                    let killed = kill_where(&pool, request_map, |rq| rq.query.chart == chart).await;
+                    count_kills("chart", killed);
+                }
+            }
+            Message::KillRequest((uuid, request_id, _conn)) => {
+                if let Some(request_map) = active_queries.get_mut(&uuid) {
+                    let killed =
+                        kill_where(&pool, request_map, |rq| rq.query.request_id == request_id)
+                            .await;
+                    count_kills("request", killed);
+                }
+            }
+            Message::List(reply) => {
+                let _ = reply.send(summarise(&active_queries));
+            }
+            Message::KillAll(reply) => {
+                shutting_down = true;
+                let mut killed = 0;
+                for request_map in active_queries.values_mut() {
+                    killed += kill_where(&pool, request_map, |_| true).await;
+                }
+                tracing::info!("reaper killed {killed} queries on shutdown");
+                count_kills("shutdown", killed);
+                active_queries.clear();
+                let _ = reply.send(());
+            }
         };
+
+        let active = active_queries.values().map(|m| m.len()).sum::<usize>();
+        gauge!("reaper_active_queries").set(active as f64);
+    }
+}
+
+/// A query registered with the reaper for as long as its handler runs it.
+///
+/// If the handler is dropped before the query finishes, e.g. because the client
+/// disconnected, the watch asks the reaper to kill the query. A watch that holds
+/// the connection its query runs on gives the connection to the reaper whenever
+/// the query doesn't finish, so that it's only reused once the query is killed.
+
+pub struct Watch {
+    sender: Sender<Message>,
+    query: ActiveQuery,
+    token: CancellationToken,
+    timeout: Duration,
+    done: bool,
+    conn: Option<Connection>,
+}
+
+impl Watch {
+    /// Registers a query with the reaper, returning `TooManyQueries` if the reaper
+    /// refuses it. The connection is the one the query runs on, if it has one of
+    /// its own, which the watch holds until the query finishes.
+
+    pub async fn register(
+        sender: &Sender<Message>,
+        query: ActiveQuery,
+        timeout: Duration,
+        conn: Option<Connection>,
+    ) -> Result<Watch, AppError> {
+        let token = CancellationToken::new();
+        let (reply, accepted) = oneshot::channel();
+        sender
+            .send(Message::Register((query.clone(), token.clone(), reply)))
+            .await?;
+
+        let mut watch = Watch {
+            sender: sender.clone(),
+            query,
+            token,
+            timeout,
+            done: false,
+            conn,
+        };
+        if !accepted.await? {
+            watch.done = true;
+            return Err(AppError::TooManyQueries);
+        }
+        Ok(watch)
+    }
+
+    /// Runs the query until it finishes, is cancelled, or times out, and then
+    /// deregisters it. Returns `Cancelled` if the reaper cancelled the query, and
+    /// `Timeout` if it ran for longer than the timeout, in which case the reaper is
+    /// asked to kill it.
+
+    pub async fn run<T>(
+        self,
+        query: impl Future<Output = Result<T, AppError>>,
+    ) -> Result<T, AppError> {
+        let response = tokio::select! {
+            res = query => res,
+            _ = self.token.cancelled() => Err(AppError::Cancelled),
+            _ = sleep(self.timeout) => Err(AppError::Timeout),
+        };
+        self.finish(response).await
+    }
+
+    /// Runs the query on the watch's connection, like `run`. If the query doesn't
+    /// finish, the connection is given to the reaper, which returns it to the pool
+    /// once the query is killed.
+
+    pub async fn run_on<T>(
+        mut self,
+        query: impl for<'c> FnOnce(&'c mut Connection) -> BoxFuture<'c, Result<T, AppError>>,
+    ) -> Result<T, AppError> {
+        let Some(conn) = self.conn.as_mut() else {
+            return self.finish(Err(AppError::GenericError)).await;
+        };
+        let response = tokio::select! {
+            res = query(conn) => res,
+            _ = self.token.cancelled() => Err(AppError::Cancelled),
+            _ = sleep(self.timeout) => Err(AppError::Timeout),
+        };
+        self.finish(response).await
+    }
+
+    /// Deregisters a query that finished, or asks the reaper to kill one that was
+    /// cancelled or timed out, handing it the connection.
+    ///
+    /// A cancelled query was already killed or is being killed by the reaper, which
+    /// handles messages in order, so the connection is only dropped once it's done.
+
+    async fn finish<T>(mut self, response: Result<T, AppError>) -> Result<T, AppError> {
+        self.done = true;
+        match response {
+            Err(AppError::Cancelled) => counter!("queries_cancelled_total").increment(1),
+            Err(AppError::Timeout) => counter!("queries_timed_out_total").increment(1),
+            _ => {}
+        }
+        let message = match response {
+            Err(AppError::Timeout) | Err(AppError::Cancelled) => {
+                if matches!(response, Err(AppError::Timeout)) {
+                    tracing::warn!("query for {} timed out", self.query.chart);
+                }
+                let conn = self.conn.take();
+                Message::KillRequest((self.query.uuid, self.query.request_id, conn))
+            }
+            _ => Message::Deregister(self.query.clone()),
+        };
+        self.sender.send(message).await?;
+
+        response
+    }
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        if self.done {
+            return;
+        }
+        let conn = self.conn.take();
+        let message = Message::KillRequest((self.query.uuid, self.query.request_id, conn));
+        // the connection mustn't go back to the pool before the kill, so wait for
+        // room in the channel rather than dropping it with the message
+        if let Err(TrySendError::Full(message)) = self.sender.try_send(message) {
+            let sender = self.sender.clone();
+            tokio::spawn(async move { sender.send(message).await });
+        }
+    }
+}
+
+/// Counts the queries the reaper killed for the given reason: the kind of kill
+/// message, or `expired` for queries killed by the sweep.
+
+fn count_kills(reason: &'static str, killed: usize) {
+    counter!("reaper_killed_queries_total", "reason" => reason).increment(killed as u64);
+}
+
+/// Returns a summary of every active query in the store, grouped by user.
+
+fn summarise(active_queries: &HashMap<Uuid, HashMap<Uuid, RegisteredQuery>>) -> Vec<UserQueries> {
+    active_queries
+        .iter()
+        .filter(|(_, request_map)| !request_map.is_empty())
+        .map(|(uuid, request_map)| UserQueries {
+            uuid: *uuid,
+            queries: request_map
+                .values()
+                .map(|rq| QuerySummary {
+                    request_id: rq.query.request_id,
+                    conn_id: rq.query.conn_id,
+                    kind: if rq.query.speech { "speech" } else { "chart" },
+                    chart: rq.query.chart.clone(),
+                    age: rq.started.elapsed().as_secs_f64(),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Kills the queries in a user's store of active queries that match the predicate,
+/// and drops their details from the store. Other queries are left running. Returns
+/// the number of queries killed.
+
+async fn kill_where<F>(
+    pool: &Pool<AsyncMysqlConnection>,
+    request_map: &mut HashMap<Uuid, RegisteredQuery>,
+    predicate: F,
+) -> usize
+where
+    F: Fn(&RegisteredQuery) -> bool,
+{
+    // cancel the tokens *before* killing the queries so the route handlers return
+    // the proper 204 code indicating a cancel, instead of an error
+    let killed = request_map
+        .iter()
+        .filter_map(|(request_id, rq)| {
+            if predicate(rq) {
+                rq.token.cancel();
+                Some((*request_id, rq.query.conn_id))
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<(Uuid, Option<i32>)>>();
+    if killed.is_empty() {
+        return 0;
+    }
+
+    if killed.iter().any(|(_, conn_id)| conn_id.is_some()) {
+        if let Ok(mut conn) = pool.get().await {
+            for conn_id in killed.iter().filter_map(|(_, conn_id)| conn_id.as_ref()) {
+                let _ = kill_connection_id(&mut conn, conn_id).await;
+            }
+        }
+    }
+    for (request_id, _) in killed.iter() {
+        request_map.remove(request_id);
     }
+    killed.len()
 }
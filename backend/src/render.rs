@@ -0,0 +1,185 @@
+use crate::error::AppError;
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use common::models::{BreakdownType, BreakdownTypeParseError};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use plotters::prelude::{BitMapBackend, IntoDrawingArea, SVGBackend};
+use plotting::{draw_image, Chart};
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// The width of rendered chart images. Together with the height, this is the size
+/// recommended for link preview images.
+
+pub const WIDTH: u32 = 1200;
+
+/// The height of rendered chart images.
+
+pub const HEIGHT: u32 = 630;
+
+/// The query parameters of a render request.
+
+#[derive(Deserialize)]
+pub struct RenderRequest {
+    /// The search term to render the chart for.
+    pub search: String,
+
+    /// Whether to show total counts instead of adjusted scores.
+    #[serde(default)]
+    pub counts: bool,
+}
+
+/// The query parameters of a shared search page.
+
+#[derive(Deserialize)]
+pub struct ShareRequest {
+    /// The search term that was shared, if any.
+    pub q: Option<String>,
+}
+
+/// A chart that can be rendered server-side.
+
+pub enum RenderedChart {
+    Breakdown(BreakdownType),
+    Population,
+}
+
+impl fmt::Display for RenderedChart {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RenderedChart::Breakdown(breakdown_type) => write!(f, "{breakdown_type} breakdown"),
+            RenderedChart::Population => write!(f, "population density plot"),
+        }
+    }
+}
+
+/// An image format a chart can be rendered to.
+
+pub enum ImageFormat {
+    Png,
+    Svg,
+}
+
+/// The file requested from the render route, e.g. `party.png` or `population.svg`.
+
+pub struct RenderTarget {
+    pub chart: RenderedChart,
+    pub format: ImageFormat,
+}
+
+impl FromStr for RenderTarget {
+    type Err = BreakdownTypeParseError;
+    fn from_str(input: &str) -> Result<RenderTarget, Self::Err> {
+        let (chart, extension) = input.rsplit_once('.').ok_or(BreakdownTypeParseError)?;
+        let chart = match chart {
+            "population" => RenderedChart::Population,
+            _ => RenderedChart::Breakdown(BreakdownType::from_str(chart)?),
+        };
+        let format = match extension.to_lowercase().as_str() {
+            "png" => ImageFormat::Png,
+            "svg" => ImageFormat::Svg,
+            _ => return Err(BreakdownTypeParseError),
+        };
+        Ok(RenderTarget { chart, format })
+    }
+}
+
+impl ImageFormat {
+    /// Renders a chart with the given caption to an image response in this format,
+    /// which link preview scrapers and browsers may cache for the given number of
+    /// seconds.
+    ///
+    /// Png images are drawn into an rgb buffer by plotters' bitmap backend and
+    /// encoded, and svg images come straight from plotters' svg backend.
+
+    pub fn render<C: Chart>(
+        &self,
+        chart: &C,
+        caption: &str,
+        max_age: u64,
+    ) -> Result<Response, AppError> {
+        let cache_control = format!("public, max-age={max_age}");
+        match self {
+            ImageFormat::Png => {
+                let mut buffer = vec![0; (WIDTH * HEIGHT * 3) as usize];
+                {
+                    let drawing_area = BitMapBackend::with_buffer(&mut buffer, (WIDTH, HEIGHT))
+                        .into_drawing_area();
+                    draw_image(&drawing_area, chart, WIDTH as f64, 1.0, Some(caption))?;
+                }
+
+                let mut png = vec![];
+                let mut encoder = png::Encoder::new(&mut png, WIDTH, HEIGHT);
+                encoder.set_color(png::ColorType::Rgb);
+                encoder.set_depth(png::BitDepth::Eight);
+                let mut writer = encoder.write_header()?;
+                writer.write_image_data(&buffer)?;
+                writer.finish()?;
+
+                Ok((
+                    [
+                        (header::CONTENT_TYPE, "image/png"),
+                        (header::CACHE_CONTROL, cache_control.as_str()),
+                    ],
+                    png,
+                )
+                    .into_response())
+            }
+            ImageFormat::Svg => {
+                let mut svg = String::new();
+                {
+                    let drawing_area =
+                        SVGBackend::with_string(&mut svg, (WIDTH, HEIGHT)).into_drawing_area();
+                    draw_image(&drawing_area, chart, WIDTH as f64, 1.0, Some(caption))?;
+                }
+
+                Ok((
+                    [
+                        (header::CONTENT_TYPE, "image/svg+xml"),
+                        (header::CACHE_CONTROL, cache_control.as_str()),
+                    ],
+                    svg,
+                )
+                    .into_response())
+            }
+        }
+    }
+}
+
+/// Returns the app's index page with OpenGraph and twitter card meta tags for a
+/// shared search term added to its head, so link previews show the search's party
+/// breakdown chart.
+///
+/// The public url is the scheme and host the app is served from, which scrapers
+/// need to fetch the preview image. If it's empty the urls are left relative.
+
+pub fn with_share_tags(index: &str, search: &str, public_url: &str) -> String {
+    let encoded = utf8_percent_encode(search, NON_ALPHANUMERIC).to_string();
+    let title = escape_html(&format!("\"{search}\" - House Words"));
+    let description = escape_html(&format!(
+        "Who says \"{search}\" the most? Word counts per 100,000 words spoken, broken down by party."
+    ));
+    let tags = format!(
+        r#"    <meta property="og:type" content="website" />
+    <meta property="og:title" content="{title}" />
+    <meta property="og:description" content="{description}" />
+    <meta property="og:url" content="{public_url}/search?q={encoded}" />
+    <meta property="og:image" content="{public_url}/api/render/party.png?search={encoded}" />
+    <meta property="og:image:width" content="{WIDTH}" />
+    <meta property="og:image:height" content="{HEIGHT}" />
+    <meta name="twitter:card" content="summary_large_image" />
+  "#
+    );
+    index.replacen("</head>", &format!("{tags}</head>"), 1)
+}
+
+/// Escapes a string for use in html text or a quoted attribute.
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
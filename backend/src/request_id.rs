@@ -0,0 +1,36 @@
+use axum::{extract::Request, middleware::Next, response::Response};
+use std::future::Future;
+
+tokio::task_local! {
+    /// The id of the request currently being handled.
+    static REQUEST_ID: String;
+}
+
+/// Middleware that makes the request's `x-request-id` header available to the
+/// rest of the request's handling, so that error responses can report it.
+
+pub async fn scope_request_id(request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get("x-request-id")
+        .and_then(|id| id.to_str().ok())
+        .unwrap_or_default()
+        .to_owned();
+    REQUEST_ID.scope(id, next.run(request)).await
+}
+
+/// Returns the id of the request currently being handled, if there is one.
+
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID
+        .try_with(|id| id.clone())
+        .ok()
+        .filter(|id| !id.is_empty())
+}
+
+/// Wraps a future so that it runs with the id of the request currently being
+/// handled, for tasks spawned to finish a request's work after its handler returns.
+
+pub fn in_request_scope<F: Future>(future: F) -> impl Future<Output = F::Output> {
+    REQUEST_ID.scope(current_request_id().unwrap_or_default(), future)
+}
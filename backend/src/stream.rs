@@ -0,0 +1,231 @@
+//! Streamed chart data.
+//!
+//! Shared chart queries count the search term over one range of transcripts at a
+//! time. The streaming routes send the rows summed over the ranges counted so far
+//! each time a range finishes, as lines of json, so that charts can be drawn
+//! before their queries finish.
+
+use crate::audit;
+use crate::coalesce::{Subscription, Update};
+use crate::dummy_db::*;
+use crate::error::AppError;
+use crate::handlers::{breakdown_flight, checked_search, population_flight, wait};
+use crate::reaper::Watch;
+use crate::request_id::in_request_scope;
+use crate::AppState;
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, Path, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+use common::models::*;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use tokio::sync::mpsc;
+
+/// The number of speakers shown in the speaker breakdown.
+
+const TOP_SPEAKERS: usize = 10;
+
+/// A chart row that can be summed over the ranges of transcripts its query runs
+/// over.
+
+pub trait Aggregate {
+    /// The id of the category, speaker, or riding the row is for.
+    fn id(&self) -> i32;
+
+    /// Adds the counts of a row for the same id from another range of transcripts.
+    /// Scores are counts per 100,000 words spoken in total, so they add up too.
+    fn add(&mut self, other: &Self);
+}
+
+impl Aggregate for BreakdownResponse {
+    fn id(&self) -> i32 {
+        self.id
+    }
+
+    fn add(&mut self, other: &Self) {
+        self.count += other.count;
+        self.score += other.score;
+    }
+}
+
+impl Aggregate for PopulationResponse {
+    fn id(&self) -> i32 {
+        self.id
+    }
+
+    fn add(&mut self, other: &Self) {
+        self.count += other.count;
+        self.score += other.score;
+    }
+}
+
+/// Adds the rows of a chunk to the running totals, by id.
+
+pub fn merge<R: Aggregate>(totals: &mut Vec<R>, chunk: Vec<R>) {
+    for row in chunk {
+        match totals.iter_mut().find(|total| total.id() == row.id()) {
+            Some(total) => total.add(&row),
+            None => totals.push(row),
+        }
+    }
+}
+
+/// Returns the speakers who said the search term the most times, most first.
+
+pub fn top_speakers(rows: &[BreakdownResponse]) -> Vec<BreakdownResponse> {
+    let mut rows = rows.to_vec();
+    rows.sort_by_key(|row| Reverse(row.count));
+    rows.truncate(TOP_SPEAKERS);
+    rows
+}
+
+/// Stream the word count breakdown for the given word, sending the partial
+/// breakdown as each range of transcripts is counted, or the precomputed breakdown
+/// as a single chunk. See `breakdown` and `stream`.
+
+pub async fn stream_breakdown(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(breakdown_type): Path<String>,
+    Json(payload): Json<DataRequest>,
+) -> Result<Response, AppError> {
+    audit::record_request(&payload);
+    let search = checked_search(&payload.search)?;
+    let breakdown_type = BreakdownType::from_str(breakdown_type.as_str())?;
+    if state.connection_pool.is_some() {
+        let chart = format!("breakdown/{breakdown_type}");
+        if let Some(rows) = state.results.breakdown.get(&chart, &search) {
+            return Ok(finished(rows));
+        }
+        let watch = wait(&state, &payload, chart, addr).await?;
+        let subscription = breakdown_flight(&state, breakdown_type, &search, state.stream_chunks)?;
+        Ok(stream(watch, subscription))
+    } else {
+        Ok(finished(dummy_get_breakdown_word_count(breakdown_type)))
+    }
+}
+
+/// Stream the population data for the given word, sending the partial data as
+/// each range of transcripts is counted, or the precomputed data as a single chunk.
+/// See `population` and `stream`.
+
+pub async fn stream_population(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Json(payload): Json<DataRequest>,
+) -> Result<Response, AppError> {
+    audit::record_request(&payload);
+    let search = checked_search(&payload.search)?;
+    if state.connection_pool.is_some() {
+        let chart = "population".to_string();
+        if let Some(rows) = state.results.population.get(&chart, &search) {
+            return Ok(finished(rows));
+        }
+        let watch = wait(&state, &payload, chart, addr).await?;
+        let subscription = population_flight(&state, &search, state.stream_chunks)?;
+        Ok(stream(watch, subscription))
+    } else {
+        Ok(finished(dummy_get_population_word_count()))
+    }
+}
+
+/// Returns a streamed response that sends each update of a shared query as a line
+/// of json, holding a `StreamMessage`.
+///
+/// The stream is fed by its own task, which waits on the query under the request's
+/// watch, so the request can be cancelled or time out like any other. Errors are
+/// sent as the last line, since the response status has already been sent. If the
+/// request is cancelled, or the client goes away, the stream ends without a final
+/// chunk.
+
+fn stream<R>(watch: Watch, subscription: Subscription<Vec<R>>) -> Response
+where
+    R: Serialize + Clone + Send + Sync + 'static,
+{
+    let (lines, receiver) = mpsc::channel(4);
+    tokio::spawn(in_request_scope(async move {
+        let result = watch.run(forward(subscription, &lines)).await;
+        if let Some((_, body)) = result.err().and_then(|error| error.status_and_body()) {
+            if let Ok(line) = to_line(&StreamMessage::<R>::Error(body)) {
+                let _ = lines.send(line).await;
+            }
+        }
+    }));
+
+    let body = futures::stream::unfold(receiver, |mut receiver| async move {
+        let line = receiver.recv().await?;
+        Some((Ok::<String, Infallible>(line), receiver))
+    });
+    (
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(body),
+    )
+        .into_response()
+}
+
+/// Sends each update of a shared query down the channel as a line of json until the
+/// query finishes. Returns `Cancelled` if the client went away.
+
+async fn forward<R>(
+    mut subscription: Subscription<Vec<R>>,
+    lines: &mpsc::Sender<String>,
+) -> Result<(), AppError>
+where
+    R: Serialize + Clone,
+{
+    let mut total = 1;
+    loop {
+        let chunk = match subscription.next().await? {
+            Update::Running(None) => continue,
+            Update::Running(Some(partial)) => {
+                total = partial.total;
+                StreamChunk {
+                    done: partial.done,
+                    total,
+                    rows: partial.result,
+                }
+            }
+            Update::Finished(result) => {
+                let chunk = StreamChunk {
+                    done: total,
+                    total,
+                    rows: result?,
+                };
+                let line = to_line(&StreamMessage::Chunk(chunk))?;
+                return lines.send(line).await.map_err(|_| AppError::Cancelled);
+            }
+        };
+        let line = to_line(&StreamMessage::Chunk(chunk))?;
+        lines.send(line).await.map_err(|_| AppError::Cancelled)?;
+    }
+}
+
+/// Returns a streamed response holding only the final rows, for precomputed results
+/// and dummy mode.
+
+fn finished<R: Serialize>(rows: Vec<R>) -> Response {
+    let chunk = StreamChunk {
+        done: 1,
+        total: 1,
+        rows,
+    };
+    match to_line(&StreamMessage::Chunk(chunk)) {
+        Ok(line) => ([(header::CONTENT_TYPE, "application/x-ndjson")], line).into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+/// Serializes a stream message as a line of json.
+
+fn to_line<R: Serialize>(message: &StreamMessage<R>) -> Result<String, AppError> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    Ok(line)
+}
@@ -0,0 +1,143 @@
+//! Prometheus metrics.
+//!
+//! Metrics are recorded throughout the backend with the `metrics` macros, and
+//! rendered in the Prometheus text format by a `/metrics` route served on its own
+//! port, so that it isn't exposed alongside the app. If no metrics port is set, no
+//! recorder is installed and recording does nothing.
+
+use crate::render::{RenderTarget, RenderedChart};
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use common::models::BreakdownType;
+use diesel_async::{pooled_connection::bb8::Pool, AsyncMysqlConnection};
+use metrics::{counter, gauge, histogram};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use std::str::FromStr;
+use tokio::time::{sleep, Duration, Instant};
+
+/// The upper bounds, in seconds, of the request latency histogram buckets. Chart
+/// queries over the whole corpus can take tens of seconds.
+
+const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0,
+];
+
+/// How often the recorder drains its histograms.
+
+const UPKEEP_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The state of the metrics route.
+
+#[derive(Clone)]
+pub struct MetricsState {
+    /// A handle to the installed recorder, which renders the metrics.
+    pub handle: PrometheusHandle,
+
+    /// The app's connection pool, whose utilisation is reported when the metrics
+    /// are scraped. None in dummy mode.
+    pub connection_pool: Option<Pool<AsyncMysqlConnection>>,
+}
+
+/// Installs the global Prometheus recorder and starts its upkeep task, returning a
+/// handle to render the metrics with.
+///
+/// Panics on error, since the recorder can only fail to install if one has already
+/// been installed.
+
+pub fn install_recorder() -> PrometheusHandle {
+    let handle = PrometheusBuilder::new()
+        .set_buckets_for_metric(
+            Matcher::Full("http_request_duration_seconds".to_owned()),
+            LATENCY_BUCKETS,
+        )
+        .expect("couldn't set latency buckets")
+        .install_recorder()
+        .expect("couldn't install metrics recorder");
+
+    let upkeep = handle.clone();
+    tokio::spawn(async move {
+        loop {
+            sleep(UPKEEP_INTERVAL).await;
+            upkeep.run_upkeep();
+        }
+    });
+    handle
+}
+
+/// Return all metrics in the Prometheus text format, after updating the connection
+/// pool gauges.
+
+pub async fn metrics(State(state): State<MetricsState>) -> String {
+    if let Some(pool) = &state.connection_pool {
+        let pool_state = pool.state();
+        let in_use = pool_state.connections - pool_state.idle_connections;
+        gauge!("db_pool_connections").set(pool_state.connections);
+        gauge!("db_pool_idle_connections").set(pool_state.idle_connections);
+        gauge!("db_pool_in_use_connections").set(in_use);
+        counter!("db_pool_waited_total").absolute(pool_state.statistics.get_waited);
+        counter!("db_pool_timed_out_total").absolute(pool_state.statistics.get_timed_out);
+    }
+    state.handle.render()
+}
+
+/// Middleware that counts the requests to each route, and records how long they
+/// took, labelled by route, chart, and response status.
+///
+/// This is applied as a route layer, so only requests that matched a route are
+/// recorded, keeping the labels to a known set.
+
+pub async fn track_requests(request: Request, next: Next) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_owned())
+        .unwrap_or_default();
+    let chart = chart_label(&route, request.uri().path());
+    let start = Instant::now();
+
+    let response = next.run(request).await;
+
+    let labels = [
+        ("route", route),
+        ("chart", chart),
+        ("status", response.status().as_u16().to_string()),
+    ];
+    counter!("http_requests_total", &labels).increment(1);
+    histogram!("http_request_duration_seconds", &labels).record(start.elapsed().as_secs_f64());
+    response
+}
+
+/// Returns the chart a request is for, taken from the breakdown type or rendered
+/// file in its path. Returns `none` if the route has no chart, and `invalid` if the
+/// chart doesn't exist, so that clients can't add labels of their own.
+
+fn chart_label(route: &str, path: &str) -> String {
+    let Some((template, segment)) = route
+        .split('/')
+        .zip(path.split('/'))
+        .find(|(template, _)| matches!(*template, "{type}" | "{breakdown}" | "{file}"))
+    else {
+        return "none".to_owned();
+    };
+
+    if template == "{file}" {
+        match RenderTarget::from_str(segment) {
+            Ok(RenderTarget {
+                chart: RenderedChart::Breakdown(breakdown_type),
+                ..
+            }) => breakdown_type.to_string(),
+            Ok(RenderTarget {
+                chart: RenderedChart::Population,
+                ..
+            }) => "population".to_owned(),
+            Err(_) => "invalid".to_owned(),
+        }
+    } else {
+        BreakdownType::from_str(segment)
+            .map(|breakdown_type| breakdown_type.to_string())
+            .unwrap_or_else(|_| "invalid".to_owned())
+    }
+}
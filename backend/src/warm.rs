@@ -0,0 +1,262 @@
+//! Precomputed results for popular search terms.
+//!
+//! A background job periodically runs every chart query for a configured list of
+//! terms and the most searched terms of the last week, and keeps the results, so
+//! that requests for those terms are answered straight away, even on a cold start,
+//! without touching the database. Results are replaced as each run recomputes
+//! them, and forgotten once their term is no longer warmed.
+
+use crate::analytics::Analytics;
+use crate::handlers::{shared_breakdown, shared_dispersion, shared_population};
+use crate::AppState;
+use common::models::{
+    BreakdownResponse, BreakdownType, DispersionResponse, PopulationResponse, TrendingPeriod,
+};
+use common::search::{normalise, validate};
+use metrics::counter;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tokio::time::{sleep, Duration};
+use tokio_util::sync::CancellationToken;
+
+/// The breakdown types every warmed term is precomputed for.
+
+const BREAKDOWN_TYPES: [BreakdownType; 5] = [
+    BreakdownType::Party,
+    BreakdownType::Gender,
+    BreakdownType::Province,
+    BreakdownType::Class,
+    BreakdownType::Speaker,
+];
+
+/// The breakdown types the dispersion of every warmed term is precomputed for.
+/// There's no dispersion of the speaker breakdown, since each speaker is their own
+/// category.
+
+const DISPERSION_TYPES: [BreakdownType; 4] = [
+    BreakdownType::Party,
+    BreakdownType::Gender,
+    BreakdownType::Province,
+    BreakdownType::Class,
+];
+
+/// How long the job waits after the backend starts before its first run, so that
+/// startup isn't slowed down by a burst of queries.
+
+const STARTUP_DELAY: Duration = Duration::from_secs(5);
+
+/// The terms to precompute results for, and how often.
+
+pub struct WarmConfig {
+    /// Terms that are always precomputed.
+    pub terms: Vec<String>,
+
+    /// The number of the last week's most searched terms also precomputed.
+    pub top: usize,
+
+    /// How long to wait between runs.
+    pub interval: Duration,
+}
+
+/// Precomputed chart results of one type, keyed by the chart, named by its api
+/// path, and the normalised search term.
+
+pub struct Precomputed<T> {
+    results: Arc<Mutex<HashMap<(String, String), T>>>,
+}
+
+impl<T> Clone for Precomputed<T> {
+    fn clone(&self) -> Self {
+        Precomputed {
+            results: self.results.clone(),
+        }
+    }
+}
+
+impl<T> Default for Precomputed<T> {
+    fn default() -> Self {
+        Precomputed {
+            results: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl<T: Clone> Precomputed<T> {
+    /// Returns the precomputed result of the chart for the search term, if there is
+    /// one.
+
+    pub fn get(&self, chart: &str, search: &str) -> Option<T> {
+        let key = (chart.to_owned(), search.to_owned());
+        let result = self
+            .results
+            .lock()
+            .ok()
+            .and_then(|results| results.get(&key).cloned());
+        let outcome = if result.is_some() { "hit" } else { "miss" };
+        counter!("result_cache_requests_total", "outcome" => outcome).increment(1);
+        result
+    }
+
+    fn insert(&self, chart: String, search: &str, result: T) {
+        if let Ok(mut results) = self.results.lock() {
+            results.insert((chart, search.to_owned()), result);
+        }
+    }
+
+    /// Forgets the results of terms that are no longer warmed.
+
+    fn retain(&self, terms: &HashSet<String>) {
+        if let Ok(mut results) = self.results.lock() {
+            results.retain(|(_, term), _| terms.contains(term));
+        }
+    }
+}
+
+/// The precomputed results of each data route.
+
+#[derive(Clone, Default)]
+pub struct Results {
+    pub breakdown: Precomputed<Vec<BreakdownResponse>>,
+    pub dispersion: Precomputed<Vec<DispersionResponse>>,
+    pub population: Precomputed<Vec<PopulationResponse>>,
+}
+
+/// Returns the terms to warm: the configured terms and the most searched terms of
+/// the last week, normalised, without duplicates or terms that can't be searched.
+
+fn warm_terms(config: &WarmConfig, analytics: &Analytics) -> Vec<String> {
+    let trending = analytics.trending(TrendingPeriod::Week);
+    let popular = trending.terms.into_iter().take(config.top).map(|t| t.term);
+
+    let mut seen = HashSet::new();
+    config
+        .terms
+        .iter()
+        .map(|term| normalise(term))
+        .chain(popular)
+        .filter(|term| validate(term).is_ok() && seen.insert(term.clone()))
+        .collect()
+}
+
+/// Periodically recomputes the results of every chart for the warmed terms, one
+/// query at a time, until shutdown starts.
+
+pub async fn warm(state: AppState, config: WarmConfig, shutdown: CancellationToken) {
+    let mut delay = STARTUP_DELAY;
+    loop {
+        tokio::select! {
+            _ = sleep(delay) => {},
+            _ = shutdown.cancelled() => return,
+        }
+        delay = config.interval;
+
+        let terms = warm_terms(&config, &state.analytics);
+        let warmed = terms.iter().cloned().collect::<HashSet<_>>();
+        state.results.breakdown.retain(&warmed);
+        state.results.dispersion.retain(&warmed);
+        state.results.population.retain(&warmed);
+        tracing::info!("precomputing results for {} terms", terms.len());
+
+        let Some(failed) = run(&state, &terms, &shutdown).await else {
+            return;
+        };
+        if failed > 0 {
+            tracing::warn!("{failed} queries failed while precomputing results");
+        }
+    }
+}
+
+/// Recomputes the results of every chart for the terms, returning the number of
+/// queries that failed, or None if shutdown started before they all ran.
+///
+/// The queries are shared with identical user requests that arrive while they run,
+/// and are watched by the reaper like any other. A query that fails leaves the
+/// term's previous result in place.
+
+async fn run(state: &AppState, terms: &[String], shutdown: &CancellationToken) -> Option<usize> {
+    let mut failed = 0;
+    for term in terms {
+        for breakdown_type in BREAKDOWN_TYPES {
+            if shutdown.is_cancelled() {
+                return None;
+            }
+            let chart = format!("breakdown/{breakdown_type}");
+            match shared_breakdown(state, breakdown_type, term).await {
+                Ok(rows) => state.results.breakdown.insert(chart, term, rows),
+                Err(_) => failed += 1,
+            }
+        }
+        for breakdown_type in DISPERSION_TYPES {
+            if shutdown.is_cancelled() {
+                return None;
+            }
+            let chart = format!("dispersion/{breakdown_type}");
+            match shared_dispersion(state, breakdown_type, term).await {
+                Ok(rows) => state.results.dispersion.insert(chart, term, rows),
+                Err(_) => failed += 1,
+            }
+        }
+        if shutdown.is_cancelled() {
+            return None;
+        }
+        match shared_population(state, term).await {
+            Ok(rows) => state
+                .results
+                .population
+                .insert("population".to_owned(), term, rows),
+            Err(_) => failed += 1,
+        }
+    }
+    Some(failed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coalesce::Flights;
+    use crate::config::AnalyticsConfig;
+    use std::path::PathBuf;
+    use tokio::sync::mpsc;
+
+    /// Returns the state of a backend in dummy mode, without saved analytics.
+
+    fn dummy_state() -> AppState {
+        let (sender, _) = mpsc::channel(1);
+        let analytics = AnalyticsConfig {
+            save: false,
+            ..AnalyticsConfig::default()
+        };
+        AppState {
+            connection_pool: None,
+            sender,
+            data_source: "federal".to_owned(),
+            static_dir: PathBuf::new(),
+            demo_dir: PathBuf::new(),
+            public_url: String::new(),
+            admin_token: None,
+            query_timeout: Duration::from_secs(1),
+            stream_chunks: 1,
+            render_max_age: 0,
+            flights: Flights::default(),
+            analytics: Analytics::load(&analytics),
+            results: Results::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn warming_a_dummy_state_has_no_failures() {
+        let state = dummy_state();
+        let terms = vec!["housing".to_owned()];
+        let failed = run(&state, &terms, &CancellationToken::new()).await;
+        assert_eq!(failed, Some(0));
+
+        let dispersion = &state.results.dispersion;
+        assert!(dispersion.get("dispersion/party", "housing").is_some());
+        assert!(dispersion.get("dispersion/speaker", "housing").is_none());
+        assert!(state
+            .results
+            .population
+            .get("population", "housing")
+            .is_some());
+    }
+}
@@ -0,0 +1,212 @@
+use serde::de::{self, DeserializeOwned, Deserializer, Visitor};
+use serde::{Deserialize, Serialize};
+
+/// Details describing where a set of exported chart data came from, written at the
+/// top of every export.
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ExportMeta {
+    /// The search term the data was generated for.
+    pub search: String,
+    /// The legislature the data was pulled from.
+    pub source: String,
+    /// The time the export was generated, in RFC 3339 format.
+    pub generated: String,
+}
+
+/// An error serializing chart data for export.
+
+#[derive(Debug)]
+pub struct ExportError;
+
+impl From<csv::Error> for ExportError {
+    fn from(_: csv::Error) -> Self {
+        ExportError
+    }
+}
+
+impl From<csv::IntoInnerError<csv::Writer<Vec<u8>>>> for ExportError {
+    fn from(_: csv::IntoInnerError<csv::Writer<Vec<u8>>>) -> Self {
+        ExportError
+    }
+}
+
+impl From<std::string::FromUtf8Error> for ExportError {
+    fn from(_: std::string::FromUtf8Error) -> Self {
+        ExportError
+    }
+}
+
+impl From<serde_json::Error> for ExportError {
+    fn from(_: serde_json::Error) -> Self {
+        ExportError
+    }
+}
+
+/// A json export, with the metadata fields sitting alongside the data.
+
+#[derive(Serialize)]
+struct JsonExport<'a, T> {
+    #[serde(flatten)]
+    meta: &'a ExportMeta,
+    data: &'a [T],
+}
+
+/// Returns the given rows as a pretty-printed json object, with the metadata as
+/// top-level fields and the rows under `data`.
+
+pub fn to_json<T: Serialize>(meta: &ExportMeta, data: &[T]) -> Result<String, ExportError> {
+    Ok(serde_json::to_string_pretty(&JsonExport { meta, data })?)
+}
+
+/// Returns a value escaped as a csv cell, quoted only if it needs to be, so that a
+/// search term can't add lines or cells to a csv export. Line breaks are replaced
+/// with spaces, since readers skip comment lines one line at a time, and would read
+/// the rest of a broken line as a row.
+
+fn csv_cell(value: &str) -> Result<String, ExportError> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer.write_record([value.replace(['\r', '\n'], " ")])?;
+    let record = String::from_utf8(writer.into_inner()?)?;
+    Ok(record.strip_suffix('\n').unwrap_or(&record).to_owned())
+}
+
+/// A deserializer that only records the field names of the struct it's asked for,
+/// and then gives up.
+
+struct FieldNames<'a>(&'a mut Option<&'static [&'static str]>);
+
+impl<'de> Deserializer<'de> for FieldNames<'_> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("not a struct"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        *self.0 = Some(fields);
+        Err(de::Error::custom("only the field names are read"))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes
+        byte_buf option unit unit_struct newtype_struct seq tuple tuple_struct map enum
+        identifier ignored_any
+    }
+}
+
+/// Returns the names of the fields of a struct, which are the columns its rows are
+/// serialized to, or None if it isn't a struct.
+
+fn field_names<T: DeserializeOwned>() -> Option<&'static [&'static str]> {
+    let mut fields = None;
+    let _ = T::deserialize(FieldNames(&mut fields));
+    fields
+}
+
+/// Returns the given rows as csv, preceded by the metadata as `#` comment lines.
+/// The metadata values are escaped like the cells of the rows, without line breaks.
+/// The header is written even if there are no rows.
+
+pub fn to_csv<T: Serialize + DeserializeOwned>(
+    meta: &ExportMeta,
+    data: &[T],
+) -> Result<String, ExportError> {
+    let mut out = format!(
+        "# search: {}\n# source: {}\n# generated: {}\n",
+        csv_cell(&meta.search)?,
+        csv_cell(&meta.source)?,
+        csv_cell(&meta.generated)?,
+    );
+
+    let mut writer = csv::Writer::from_writer(vec![]);
+    if data.is_empty() {
+        if let Some(fields) = field_names::<T>() {
+            writer.write_record(fields)?;
+        }
+    }
+    for row in data {
+        writer.serialize(row)?;
+    }
+    out.push_str(&String::from_utf8(writer.into_inner()?)?);
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::BreakdownResponse;
+
+    fn meta(search: &str) -> ExportMeta {
+        ExportMeta {
+            search: search.to_owned(),
+            source: "federal".to_owned(),
+            generated: "2025-01-01T00:00:00Z".to_owned(),
+        }
+    }
+
+    fn row(id: i32, name: &str) -> BreakdownResponse {
+        BreakdownResponse {
+            id,
+            name: name.to_owned(),
+            colour: "#ff0000".to_owned(),
+            count: 3,
+            score: 1.5,
+        }
+    }
+
+    #[test]
+    fn csv_has_a_header_and_rows() {
+        let csv = to_csv(&meta("housing"), &[row(1, "Liberal"), row(2, "NDP, Party")]).unwrap();
+        assert_eq!(
+            csv,
+            "# search: housing\n\
+             # source: federal\n\
+             # generated: 2025-01-01T00:00:00Z\n\
+             id,name,colour,count,score\n\
+             1,Liberal,#ff0000,3,1.5\n\
+             2,\"NDP, Party\",#ff0000,3,1.5\n"
+        );
+    }
+
+    #[test]
+    fn empty_csv_still_has_a_header() {
+        let csv = to_csv::<BreakdownResponse>(&meta("housing"), &[]).unwrap();
+        assert!(csv.ends_with("# generated: 2025-01-01T00:00:00Z\nid,name,colour,count,score\n"));
+    }
+
+    #[test]
+    fn csv_escapes_the_search_term() {
+        let csv = to_csv(&meta("say \"no\", then\nleave"), &[row(1, "Liberal")]).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("# search: \"say \"\"no\"\", then leave\"")
+        );
+        assert_eq!(lines.next(), Some("# source: federal"));
+
+        let mut reader = csv::ReaderBuilder::new()
+            .comment(Some(b'#'))
+            .from_reader(csv.as_bytes());
+        let rows = reader
+            .deserialize::<BreakdownResponse>()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(rows, [row(1, "Liberal")]);
+    }
+
+    #[test]
+    fn json_puts_the_metadata_beside_the_data() {
+        let json = to_json(&meta("housing"), &[row(1, "Liberal")]).unwrap();
+        let value = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+        assert_eq!(value["search"], "housing");
+        assert_eq!(value["data"][0]["name"], "Liberal");
+        assert!(value.get("date_filter").is_none());
+    }
+}
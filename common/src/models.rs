@@ -42,16 +42,107 @@ impl fmt::Display for BreakdownType {
     }
 }
 
+/// A machine-readable code for the kind of error the backend responded with.
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// Something unexpected went wrong on the server.
+    Internal,
+    /// The server has no database connections to spare.
+    Busy,
+    /// The client has sent too many requests.
+    RateLimited,
+    /// The breakdown type or chart in the url doesn't exist.
+    BadBreakdownType,
+    /// The search term was empty.
+    EmptySearch,
+    /// The search term was longer than `MAX_SEARCH_LENGTH`.
+    SearchTooLong,
+    /// The search term contained a character that can't be searched for.
+    InvalidSearch,
+    /// There is no category or speaker with the requested id.
+    UnknownId,
+    /// There is no demo dataset with the requested name.
+    UnknownDemo,
+    /// The request needs an admin token, and didn't have a valid one.
+    Unauthorized,
+    /// The api key sent isn't one the server accepts.
+    BadApiKey,
+    /// The client already has too many queries running.
+    TooManyQueries,
+    /// The query took longer than the server allows, and was stopped.
+    Timeout,
+}
+
+/// The body of an error response from the backend.
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ErrorResponse {
+    pub code: ErrorCode,
+    /// A description of the error, fit to show to users.
+    pub message: String,
+    /// The number of seconds to wait before retrying, if waiting will help.
+    pub retry_after: Option<u64>,
+    /// The id of the failed request, to match it with the server logs.
+    pub request_id: Option<String>,
+}
+
 /// A request to search the transcripts for the given search word.
+///
+/// The request id identifies this request alone, so that it can be cancelled
+/// without cancelling the user's other requests. Requests sent without one are
+/// given a random request id.
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct DataRequest {
     pub uuid: Uuid,
+    #[serde(default = "Uuid::new_v4")]
+    pub request_id: Uuid,
     pub search: String,
 }
 
+impl DataRequest {
+    /// Returns a new request from the given user, with a random request id.
+
+    pub fn new(uuid: Uuid, search: String) -> DataRequest {
+        DataRequest {
+            uuid,
+            request_id: Uuid::new_v4(),
+            search,
+        }
+    }
+}
+
 impl fmt::Display for DataRequest {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "uuid: {}\tsearch: {}", self.uuid, self.search)
+        write!(
+            f,
+            "uuid: {}\trequest id: {}\tsearch: {}",
+            self.uuid, self.request_id, self.search
+        )
+    }
+}
+
+/// The query string of a public api request for data about the given search word.
+///
+/// The uuid is optional. Requests made with one can be cancelled like those from
+/// the frontend, and requests without one are given a random uuid.
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+pub struct SearchParams {
+    /// The word or phrase to search the transcripts for.
+    pub search: String,
+    /// An id to group requests under for cancellation.
+    pub uuid: Option<Uuid>,
+}
+
+impl From<SearchParams> for DataRequest {
+    fn from(params: SearchParams) -> DataRequest {
+        DataRequest::new(params.uuid.unwrap_or_else(Uuid::new_v4), params.search)
     }
 }
 
@@ -69,9 +160,40 @@ impl fmt::Display for CancelRequest {
     }
 }
 
+/// A request to cancel the queries for one chart associated with the current
+/// user. The chart is named by its api path, e.g. `breakdown/party`.
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ChartCancelRequest {
+    pub uuid: Uuid,
+    pub chart: String,
+}
+
+impl fmt::Display for ChartCancelRequest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "uuid: {}, chart: {}", self.uuid, self.chart)
+    }
+}
+
+/// A request to cancel the queries for a single data request made by the current
+/// user.
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct SingleCancelRequest {
+    pub uuid: Uuid,
+    pub request_id: Uuid,
+}
+
+impl fmt::Display for SingleCancelRequest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "uuid: {}, request id: {}", self.uuid, self.request_id)
+    }
+}
+
 /// A response from the backend representing a speaker.
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct SpeakerResponse {
     pub id: i32,
     pub first_name: String,
@@ -95,6 +217,7 @@ impl From<SpeakerRow> for SpeakerResponse {
 /// A response from the backend representing a breakdown data point.
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct BreakdownResponse {
     pub id: i32,
     pub name: String,
@@ -119,9 +242,46 @@ pub fn to_breakdown_response(row: BreakdownRow) -> Option<BreakdownResponse> {
     })
 }
 
+/// A response from the backend representing one speaker's usage of a word within a
+/// breakdown category, used to show the dispersion of scores behind each bar.
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DispersionResponse {
+    /// The id of the breakdown category (party, gender, etc.) the speaker is in.
+    pub breakdown_id: i32,
+    /// The id of the speaker.
+    pub id: i32,
+    pub name: String,
+    /// The colour of the speaker's party.
+    pub colour: String,
+    /// The number of times the speaker spoke the requested word.
+    pub count: i64,
+    /// The number of times the speaker spoke the requested word per 100,000 words
+    /// they spoke.
+    pub score: f64,
+}
+
+/// A row returned from a SQL query, representing one speaker in a breakdown
+/// category.
+
+pub type DispersionRow = (i32, i32, String, String, Option<i64>, Option<f64>);
+
+pub fn to_dispersion_response(row: DispersionRow) -> Option<DispersionResponse> {
+    Some(DispersionResponse {
+        breakdown_id: row.0,
+        id: row.1,
+        name: row.2,
+        colour: row.3,
+        count: row.4.unwrap_or(0),
+        score: row.5?,
+    })
+}
+
 /// A response from the backend representing a population density scatterplot point.
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct PopulationResponse {
     pub id: i32,
     pub name: String,
@@ -152,6 +312,7 @@ pub fn to_population_response(row: PopulationRow) -> Option<PopulationResponse>
 /// A response from the backend representing a speech.
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct SpeechResponse {
     pub speaker: i32,
     pub text: String,
@@ -175,3 +336,138 @@ impl From<SpeechRow> for SpeechResponse {
         }
     }
 }
+
+/// A message in a streamed chart response. Each message is sent as one line of
+/// json.
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamMessage<T> {
+    /// The chart's rows, aggregated over the chunks of the corpus processed so far.
+    Chunk(StreamChunk<T>),
+    /// The query failed. This is always the last message of the stream.
+    Error(ErrorResponse),
+}
+
+/// A chart's rows, aggregated over some of the chunks of the corpus its query runs
+/// over. The chunk with `done` equal to `total` holds the final rows.
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct StreamChunk<T> {
+    pub done: usize,
+    pub total: usize,
+    pub rows: Vec<T>,
+}
+
+impl<T> StreamChunk<T> {
+    /// Whether this chunk holds the final rows.
+
+    pub fn finished(&self) -> bool {
+        self.done >= self.total
+    }
+}
+
+/// The period search analytics are summarised over.
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum TrendingPeriod {
+    /// The last 24 hours.
+    #[default]
+    Day,
+    /// The last 7 days.
+    Week,
+}
+
+impl TrendingPeriod {
+    /// Returns the length of the period in seconds.
+
+    pub fn seconds(&self) -> i64 {
+        match self {
+            TrendingPeriod::Day => 24 * 60 * 60,
+            TrendingPeriod::Week => 7 * 24 * 60 * 60,
+        }
+    }
+}
+
+impl fmt::Display for TrendingPeriod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TrendingPeriod::Day => write!(f, "day"),
+            TrendingPeriod::Week => write!(f, "week"),
+        }
+    }
+}
+
+/// The query string of a request for search analytics.
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default, Debug)]
+#[serde(default)]
+pub struct TrendingRequest {
+    pub period: TrendingPeriod,
+}
+
+/// The most searched terms and slowest queries over a period.
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct TrendingResponse {
+    pub period: TrendingPeriod,
+    /// The most searched terms, most first.
+    pub terms: Vec<TrendingTerm>,
+    /// The slowest chart queries, slowest first.
+    pub slowest: Vec<SlowQuery>,
+}
+
+/// A search term and the number of users who searched for it.
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct TrendingTerm {
+    pub term: String,
+    pub searches: usize,
+}
+
+/// A chart query and how long it took.
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct SlowQuery {
+    pub term: String,
+    /// The chart, e.g. `breakdown/party` or `population`.
+    pub chart: String,
+    pub duration_ms: u64,
+    /// Either `ok` or `error`.
+    pub outcome: String,
+    /// When the query finished, as a unix timestamp.
+    pub timestamp: i64,
+}
+
+/// The demos an info page walks through, for one data source. Each demo is named
+/// by the dataset it's fetched from, and any that are missing are left out of the
+/// walkthrough, along with the text that explains them.
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default, Debug)]
+#[serde(default)]
+pub struct DemoWalkthrough {
+    pub party: Option<DemoChart>,
+    pub speech: Option<DemoChart>,
+    pub gender: Option<DemoChart>,
+    pub province: Option<DemoChart>,
+    pub class: Option<DemoChart>,
+    pub speaker: Option<DemoChart>,
+    pub population: Option<DemoChart>,
+}
+
+/// A demo chart or speech in a walkthrough.
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct DemoChart {
+    /// The search term the demo shows the results of.
+    pub term: String,
+    /// The name of the demo dataset.
+    pub demo: String,
+    /// Paragraphs about the data source shown before the demo is introduced.
+    #[serde(default)]
+    pub intro: Vec<String>,
+    /// Paragraphs about what the demo shows, shown after it.
+    #[serde(default)]
+    pub caption: Vec<String>,
+}
@@ -0,0 +1,162 @@
+use aho_corasick::AhoCorasick;
+use regex::Regex;
+use std::fmt;
+use std::sync::LazyLock;
+
+/// The longest search term, in characters, the backend will search for.
+
+pub const MAX_SEARCH_LENGTH: usize = 100;
+
+/// The characters other than letters, digits and spaces a search term may contain.
+
+const ALLOWED_SYMBOLS: &[char] = &['-', '&', '$', '%', '/', '#', '+'];
+
+/// Matches the punctuation stripped from search terms.
+
+static PUNCTUATION: LazyLock<AhoCorasick> = LazyLock::new(|| {
+    let patterns = &[
+        ".", ",", ";", ":", "!", "?", "\'", "\"", "”", "“", "’", "‘", "(", ")", "[", "]", "{", "}",
+        "«", "»",
+    ];
+    AhoCorasick::builder().build(patterns).unwrap()
+});
+
+/// Matches runs of whitespace, which are collapsed to a single space.
+
+static WHITESPACE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"\s+"#).unwrap());
+
+/// The reason a search term can't be searched for.
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SearchError {
+    /// The search term is empty, or nothing is left of it after normalisation.
+    Empty,
+    /// The search term is longer than `MAX_SEARCH_LENGTH`.
+    TooLong,
+    /// The search term contains a character transcripts can't match.
+    BadCharacter(char),
+}
+
+impl fmt::Display for SearchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SearchError::Empty => write!(f, "please enter a search term"),
+            SearchError::TooLong => write!(
+                f,
+                "search terms can be at most {MAX_SEARCH_LENGTH} characters long"
+            ),
+            SearchError::BadCharacter(c) => write!(f, "search terms can't contain '{c}'"),
+        }
+    }
+}
+
+/// Normalises a search term the same way the transcripts were cleaned: strips
+/// punctuation, collapses whitespace, splits dashes, and lowercases.
+
+pub fn normalise(text: &str) -> String {
+    let mut clean = String::new();
+    PUNCTUATION.replace_all_with(text, &mut clean, |_, _, dst| {
+        dst.push_str("");
+        true
+    });
+
+    WHITESPACE
+        .replace_all(&clean, " ")
+        .replace("—", " ")
+        .trim()
+        .to_lowercase()
+}
+
+/// Checks that a normalised search term is neither empty nor too long, and only
+/// contains letters, digits, spaces, and a few symbols.
+
+pub fn validate(search: &str) -> Result<(), SearchError> {
+    if search.trim().is_empty() {
+        return Err(SearchError::Empty);
+    }
+    if search.chars().count() > MAX_SEARCH_LENGTH {
+        return Err(SearchError::TooLong);
+    }
+    match search
+        .chars()
+        .find(|c| !(c.is_alphanumeric() || *c == ' ' || ALLOWED_SYMBOLS.contains(c)))
+    {
+        Some(c) => Err(SearchError::BadCharacter(c)),
+        None => Ok(()),
+    }
+}
+
+/// Escapes the metacharacters of a SQL `LIKE` pattern, so that they match
+/// literally. The default escape character is a backslash.
+
+pub fn escape_like(search: &str) -> String {
+    let mut escaped = String::with_capacity(search.len());
+    for c in search.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalise_strips_punctuation_and_smart_quotes() {
+        assert_eq!(normalise("“Carbon tax!”"), "carbon tax");
+        assert_eq!(
+            normalise("Canada’s ‘housing’ (crisis)"),
+            "canadas housing crisis"
+        );
+        assert_eq!(
+            normalise("\"it's\", [she] {said}; «non»."),
+            "its she said non"
+        );
+    }
+
+    #[test]
+    fn normalise_collapses_whitespace() {
+        assert_eq!(normalise("  climate \t\n  change  "), "climate change");
+        assert_eq!(normalise("peace—keeping"), "peace keeping");
+    }
+
+    #[test]
+    fn normalise_keeps_allowed_symbols() {
+        assert_eq!(normalise("100% C-11 & Bill_2"), "100% c-11 & bill_2");
+    }
+
+    #[test]
+    fn validate_rejects_empty_terms() {
+        assert_eq!(validate(""), Err(SearchError::Empty));
+        assert_eq!(validate("   "), Err(SearchError::Empty));
+        assert_eq!(validate(&normalise("?!.")), Err(SearchError::Empty));
+    }
+
+    #[test]
+    fn validate_rejects_long_terms() {
+        let longest = "a".repeat(MAX_SEARCH_LENGTH);
+        assert_eq!(validate(&longest), Ok(()));
+        assert_eq!(validate(&format!("{longest}a")), Err(SearchError::TooLong));
+        // the length is counted in characters, not bytes
+        assert_eq!(validate(&"é".repeat(MAX_SEARCH_LENGTH)), Ok(()));
+    }
+
+    #[test]
+    fn validate_rejects_bad_characters() {
+        assert_eq!(validate("bill_2"), Err(SearchError::BadCharacter('_')));
+        assert_eq!(validate("a\\b"), Err(SearchError::BadCharacter('\\')));
+        assert_eq!(validate("50% $ & c-11 #1 a/b +"), Ok(()));
+        assert_eq!(validate("québec"), Ok(()));
+    }
+
+    #[test]
+    fn escape_like_escapes_metacharacters() {
+        assert_eq!(escape_like("100%"), "100\\%");
+        assert_eq!(escape_like("a_b"), "a\\_b");
+        assert_eq!(escape_like("a\\b"), "a\\\\b");
+        assert_eq!(escape_like("housing"), "housing");
+    }
+}
@@ -129,7 +129,11 @@ diesel::allow_columns_to_appear_in_same_group_by_clause!(
     speaker::first_name,
     speaker::last_name,
     speaker::total_words,
+    party::id,
     party::colour,
+    gender::id,
+    province::id,
+    class::id,
     riding::name,
     riding::area,
     riding::population,
@@ -9,6 +9,9 @@ pub mod plot;
 pub mod population_engine;
 pub mod breakdown_engine;
 
+// search suggestions
+pub mod popular_searches;
+
 // speech overlay
 pub mod speech_overlay;
 pub mod speech_box;
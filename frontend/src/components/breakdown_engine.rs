@@ -2,30 +2,18 @@ use crate::components::plot::{canvas_context, PlotError, Plottable};
 use crate::util::OverlaySelection;
 use common::models::BreakdownResponse;
 use common::models::BreakdownType;
+use common::models::DispersionResponse;
 use gloo::utils::window;
-use plotters::prelude::SegmentValue::CenterOf;
+use plotters::coord::Shift;
 use plotters::prelude::*;
 use plotters_canvas::CanvasBackend;
-use std::cmp::{max, min};
+use plotting::breakdown::{point_size, BarMapping, BreakdownChart};
+use plotting::{Chart, PointMapping};
+use std::cmp::min;
 use std::rc::Rc;
 use web_sys::HtmlCanvasElement;
 use yew::prelude::*;
 
-/// A breakdown chart coordinate mapping for interactivity.
-///
-/// The struct holds the top, left, bottom, and right edges of an interactive box
-/// (a bar on the chart, or both bars if we're showing total counts) and the id of
-/// of party/gender/etc. represented by that box.
-
-#[derive(Default, Clone)]
-struct CoordMapping {
-    top: i32,
-    left: i32,
-    bottom: i32,
-    right: i32,
-    id: i32,
-}
-
 /// The main engine for a breakdown chart of some type.
 
 pub struct BreakdownEngine {
@@ -45,16 +33,26 @@ pub struct BreakdownEngine {
     /// Whether we are showing total counts instead of adjusted scores.
     show_counts: bool,
 
+    /// Whether we are overlaying per-speaker scores on each bar.
+    show_dispersion: bool,
+
+    /// The per-speaker data for this chart, if it has been loaded.
+    dispersion: Rc<Vec<DispersionResponse>>,
+
     /// The id of the bar we are currently hovering over.
     hover_id: i32,
-    coord_mappings: Vec<CoordMapping>,
+
+    /// The id of the speaker point we are currently hovering over.
+    hover_member: i32,
+    coord_mappings: Vec<BarMapping>,
+    member_mappings: Vec<PointMapping>,
     get_speeches: Option<Callback<OverlaySelection>>,
 }
 
 impl BreakdownEngine {
     /// Returns a coordinate mapping that matches the mouse's position, if any.
 
-    fn mouse_mapping(&self, e: MouseEvent) -> CoordMapping {
+    fn mouse_mapping(&self, e: MouseEvent) -> BarMapping {
         let x = (e.offset_x() as f64 * self.dpr) as i32;
         let y = (e.offset_y() as f64 * self.dpr) as i32;
         for m in &self.coord_mappings {
@@ -62,7 +60,40 @@ impl BreakdownEngine {
                 return m.clone();
             }
         }
-        CoordMapping::default()
+        BarMapping::default()
+    }
+
+    /// Returns a sane speaker point display size, relative to the dpr.
+
+    fn point_size(&self) -> i32 {
+        point_size(self.dpr)
+    }
+
+    /// Returns a speaker point mapping that matches the mouse's position, if any.
+
+    fn mouse_member(&self, e: &MouseEvent) -> Option<PointMapping> {
+        let ps = self.point_size();
+        let x = (e.offset_x() as f64 * self.dpr) as i32;
+        let y = (e.offset_y() as f64 * self.dpr) as i32;
+        self.member_mappings
+            .iter()
+            .find(|m| x > m.x - ps && x < m.x + ps && y > m.y - ps && y < m.y + ps)
+            .cloned()
+    }
+
+    /// Returns a borrowed chart of the engine's data, ready to draw.
+
+    fn chart(&self) -> BreakdownChart<'_> {
+        BreakdownChart {
+            breakdown_type: &self.breakdown_type,
+            data: &self.data,
+            dispersion: if self.show_dispersion {
+                &self.dispersion
+            } else {
+                &[]
+            },
+            show_counts: self.show_counts,
+        }
     }
 }
 
@@ -76,8 +107,12 @@ impl Plottable<BreakdownResponse> for BreakdownEngine {
             window_width: 0.0,
             dpr: 1.0,
             show_counts: false,
+            show_dispersion: false,
+            dispersion: Rc::from(vec![]),
             hover_id: 0,
+            hover_member: 0,
             coord_mappings: vec![],
+            member_mappings: vec![],
             get_speeches: None,
         }
     }
@@ -88,10 +123,12 @@ impl Plottable<BreakdownResponse> for BreakdownEngine {
         &mut self,
         window_width: f64,
         show_counts: bool,
+        show_dispersion: bool,
         get_speeches: Callback<OverlaySelection>,
     ) {
         self.window_width = window_width;
         self.show_counts = show_counts;
+        self.show_dispersion = show_dispersion;
         self.get_speeches = Some(get_speeches);
     }
 
@@ -101,6 +138,22 @@ impl Plottable<BreakdownResponse> for BreakdownEngine {
         self.data = data;
     }
 
+    /// Returns the uri to request per-speaker dispersion data from. The speaker
+    /// breakdown has nothing to disperse.
+
+    fn dispersion_uri(&self) -> Option<String> {
+        match self.breakdown_type {
+            BreakdownType::Speaker => None,
+            _ => Some(format!("dispersion/{}", self.breakdown_type)),
+        }
+    }
+
+    /// Loads per-speaker dispersion data into the engine.
+
+    fn load_dispersion(&mut self, data: Rc<Vec<DispersionResponse>>) {
+        self.dispersion = data;
+    }
+
     /// Whether the engine is empty of data.
 
     fn is_empty(&self) -> bool {
@@ -110,23 +163,13 @@ impl Plottable<BreakdownResponse> for BreakdownEngine {
     /// Returns a sane calculated width for the chart.
 
     fn get_width(&self) -> u32 {
-        let segs = self.data.as_ref().len() as u32;
-        let ww = (self.window_width * 0.97) as u32;
-        let thick = min(max(segs * 90, ww), segs * 160);
-        let thin = min(max(segs * 80, ww), segs * 160);
-        match self.breakdown_type {
-            BreakdownType::Speaker => thick,
-            BreakdownType::Party => thin,
-            BreakdownType::Gender => thin,
-            BreakdownType::Province => thick,
-            BreakdownType::Class => thin,
-        }
+        self.chart().width(self.window_width)
     }
 
     /// Returns a sane calculated height for the chart.
 
     fn get_height(&self) -> u32 {
-        500
+        plotting::HEIGHT
     }
 
     /// Returns a heading for the chart.
@@ -150,144 +193,82 @@ impl Plottable<BreakdownResponse> for BreakdownEngine {
         canvas.set_width(canvas_width);
         inter_canvas.set_width(canvas_width);
 
-        // todo get rid of this clone
-        let mut data = self.data.as_ref().clone();
-        data.sort_by(|a, b| b.score.total_cmp(&a.score));
-
         let backend = CanvasBackend::with_canvas_object(canvas).ok_or(PlotError)?;
-        let drawing_area = backend.into_drawing_area();
-        let mut label_size = (self.window_width.sqrt() / 2.5 * self.dpr) as u32;
-
-        if self.breakdown_type == BreakdownType::Speaker
-            || self.breakdown_type == BreakdownType::Province
-        {
-            label_size = label_size - 4;
-        }
-
-        let x_axis = data.iter().map(|r| r.name.clone()).collect::<Vec<String>>();
-        let y_max = data
-            .iter()
-            .map(|r| r.score)
-            .max_by(|a, b| a.total_cmp(b))
-            .ok_or(PlotError)?;
-        let c_max = data
-            .iter()
-            .map(|r| r.count)
-            .max_by(|a, b| a.cmp(b))
-            .ok_or(PlotError)? as f64;
-
-        let mut chart = ChartBuilder::on(&drawing_area)
-            .x_label_area_size((40.0 * self.dpr) as u32)
-            .y_label_area_size((70.0 * self.dpr) as u32)
-            .right_y_label_area_size(if self.show_counts {
-                (60.0 * self.dpr) as u32
-            } else {
-                0
-            })
-            .caption(" ", ("sans-serif", (30.0 * self.dpr) as u32, &WHITE))
-            .build_cartesian_2d(x_axis.into_segmented(), 0.0..y_max)?
-            .set_secondary_coord(0.0..data.len() as f32, 0.0..c_max);
-
-        let bold_line = hex::decode("97948f")?;
-        let light_line = hex::decode("67635c")?;
-
-        label_size = max(
-            (label_size as f64 * (1.0 + (self.dpr * 0.1))) as u32,
-            (8.0 * self.dpr) as u32,
-        );
-        let desc_style =
-            TextStyle::from(("sans-serif", (16.0 * self.dpr) as u32).into_font()).color(&WHITE);
-        chart
-            .configure_mesh()
-            .disable_x_mesh()
-            .x_desc(format!("{}", self.breakdown_type))
-            .x_label_style(TextStyle::from(("sans-serif", label_size).into_font()).color(&WHITE))
-            .x_label_formatter(&|v| {
-                if let CenterOf(s) = v {
-                    return format!("{}", s);
-                } else {
-                    return "".to_string();
-                }
-            })
-            .y_desc("word count per 100,000")
-            .y_label_style(TextStyle::from(("sans-serif", label_size).into_font()).color(&WHITE))
-            .axis_desc_style(desc_style.clone())
-            .bold_line_style(RGBColor(bold_line[0], bold_line[1], bold_line[2]))
-            .light_line_style(RGBColor(light_line[0], light_line[1], light_line[2]))
-            .draw()?;
-
-        if self.show_counts {
-            chart
-                .configure_secondary_axes()
-                .y_desc("total word count")
-                .label_style(TextStyle::from(("sans-serif", label_size).into_font()).color(&WHITE))
-                .axis_desc_style(desc_style)
-                .y_label_formatter(&|v| format!("{}", *v as u32))
-                .draw()?;
-        }
-
-        self.coord_mappings = vec![];
-        for (i, r) in data.iter().enumerate() {
-            let left = i as f32 + if self.show_counts { 0.15 } else { 0.20 };
-            let right = i as f32 + if self.show_counts { 0.85 } else { 0.80 };
-            let mut top = r.score * (c_max / y_max);
-            if self.show_counts {
-                top = f64::max(r.count as f64, top)
-            }
-            let tl = chart.borrow_secondary().backend_coord(&(left, top));
-            let br = chart.borrow_secondary().backend_coord(&(right, 0.0));
-            self.coord_mappings.push(CoordMapping {
-                left: tl.0,
-                top: tl.1,
-                right: br.0,
-                bottom: br.1,
-                id: r.id,
-            });
-        }
-
-        // use the secondary series to allow for fine-tuned x values instead of segments
-        chart.draw_secondary_series(
-            data.iter()
-                .enumerate()
-                .map(|(i, r)| {
-                    let s_height = r.score * (c_max / y_max);
-                    let left = i as f32 + if self.show_counts { 0.15 } else { 0.20 };
-                    let right = i as f32 + if self.show_counts { 0.49 } else { 0.80 };
-
-                    let rgb = hex::decode(r.colour.clone())?;
-                    Ok(Rectangle::new(
-                        [(left, 0.0), (right, s_height)],
-                        RGBColor(rgb[0], rgb[1], rgb[2]).filled(),
-                    ))
-                })
-                .collect::<Result<Vec<Rectangle<(f32, f64)>>, PlotError>>()?,
+        let (coord_mappings, member_mappings) = self.chart().draw(
+            &backend.into_drawing_area(),
+            self.window_width,
+            self.dpr,
+            None,
         )?;
-
-        if self.show_counts {
-            chart.draw_secondary_series(
-                data.iter()
-                    .enumerate()
-                    .map(|(i, r)| {
-                        let rgb = hex::decode(r.colour.clone())?;
-                        Ok(Rectangle::new(
-                            [(i as f32 + 0.51, 0.0), (i as f32 + 0.85, r.count as f64)],
-                            RGBColor(rgb[0], rgb[1], rgb[2]).filled(),
-                        ))
-                    })
-                    .collect::<Result<Vec<Rectangle<(f32, f64)>>, PlotError>>()?,
-            )?;
-        }
+        self.coord_mappings = coord_mappings;
+        self.member_mappings = member_mappings;
         Ok(())
     }
 
+    /// Draws the chart on any plotters drawing area, at the given reference width
+    /// and pixel scale, with an optional caption.
+
+    fn draw_on<DB: DrawingBackend>(
+        &self,
+        drawing_area: &DrawingArea<DB, Shift>,
+        width: f64,
+        scale: f64,
+        caption: Option<&str>,
+    ) -> Result<(), PlotError> {
+        Ok(self.chart().draw_on(drawing_area, width, scale, caption)?)
+    }
+
     /// Handle a mouse hover event. If the user is hovering over a bar, this means
-    /// drawing an outline around it.
+    /// drawing an outline around it. If they are hovering over a speaker point,
+    /// this means outlining the point and labelling it with the speaker's name.
 
     fn hover(&mut self, e: MouseEvent, inter_canvas: HtmlCanvasElement) -> Result<(), PlotError> {
+        if let Some(mm) = self.mouse_member(&e) {
+            if mm.id != self.hover_member {
+                self.hover_id = 0;
+                self.hover_member = mm.id;
+                let context = canvas_context(&inter_canvas).ok_or(PlotError)?;
+                context.clear_rect(
+                    0.0,
+                    0.0,
+                    inter_canvas.width() as f64,
+                    inter_canvas.height() as f64,
+                );
+
+                context.begin_path();
+                context
+                    .arc(
+                        mm.x as f64,
+                        mm.y as f64,
+                        self.point_size() as f64,
+                        0.0,
+                        2.0 * std::f64::consts::PI,
+                    )
+                    .map_err(|_| PlotError)?;
+                context.set_line_width(3.0);
+                context.set_stroke_style_str("#fee17d");
+                context.stroke();
+
+                let left = (mm.x - 10) as f64;
+                let bottom = (mm.y - 10) as f64;
+                context.set_font(&format!("{}px sans-serif", (12.0 * self.dpr) as i32));
+                let ts = context.measure_text(&mm.name).map_err(|_| PlotError)?;
+                let h = ts.font_bounding_box_ascent() + 2.0;
+                context.set_fill_style_str("#121212");
+                context.fill_rect(left - 2.0, bottom - h, ts.width() + 4.0, h + 4.0);
+                context.set_fill_style_str("#fee17d");
+                context
+                    .fill_text(&mm.name, left, bottom)
+                    .map_err(|_| PlotError)?;
+            }
+            return Ok(());
+        }
+
         let cm = self.mouse_mapping(e);
 
-        if cm.id != self.hover_id {
+        if cm.id != self.hover_id || self.hover_member != 0 {
             self.hover_id = cm.id;
+            self.hover_member = 0;
             let context = canvas_context(&inter_canvas).ok_or(PlotError)?;
             context.clear_rect(
                 0.0,
@@ -312,10 +293,20 @@ impl Plottable<BreakdownResponse> for BreakdownEngine {
     }
 
     /// Handle a mouse click event. If the user clicked on a bar, this means
-    /// bringing up the speech overlay for that party/gender/etc.
+    /// bringing up the speech overlay for that party/gender/etc. If they clicked on
+    /// a speaker point, it brings up the overlay for that speaker.
 
     fn clicked(&self, e: MouseEvent) -> Result<(), PlotError> {
         if let Some(get_speeches) = &self.get_speeches {
+            if let Some(mm) = self.mouse_member(&e) {
+                get_speeches.emit(OverlaySelection {
+                    breakdown_type: BreakdownType::Speaker,
+                    id: mm.id,
+                    heading: mm.name,
+                });
+                return Ok(());
+            }
+
             let cm = self.mouse_mapping(e);
             if cm.id > 0 {
                 let heading = self
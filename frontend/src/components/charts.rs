@@ -17,6 +17,9 @@ pub struct ChartsProps {
     /// Whether the plots are showing total counts or not.
     pub show_counts: bool,
 
+    /// Whether the breakdown plots are showing per-speaker dispersion or not.
+    pub show_dispersion: bool,
+
     /// Whether the party breakdown chart is showing.
     pub show_party: bool,
 
@@ -55,6 +58,7 @@ pub fn charts(props: &ChartsProps) -> Html {
                 visible={props.show_party}
                 word={props.word.clone()}
                 show_counts={props.show_counts}
+                show_dispersion={props.show_dispersion}
                 get_speeches={&props.get_speeches}
             />
 
@@ -64,6 +68,7 @@ pub fn charts(props: &ChartsProps) -> Html {
                 visible={props.show_gender}
                 word={props.word.clone()}
                 show_counts={props.show_counts}
+                show_dispersion={props.show_dispersion}
                 get_speeches={&props.get_speeches}
             />
 
@@ -74,6 +79,7 @@ pub fn charts(props: &ChartsProps) -> Html {
                     visible={props.show_province}
                     word={props.word.clone()}
                     show_counts={props.show_counts}
+                show_dispersion={props.show_dispersion}
                     get_speeches={&props.get_speeches}
                 />
                 <Plot<BreakdownEngine, BreakdownResponse>
@@ -82,6 +88,7 @@ pub fn charts(props: &ChartsProps) -> Html {
                     visible={props.show_class}
                     word={props.word.clone()}
                     show_counts={props.show_counts}
+                show_dispersion={props.show_dispersion}
                     get_speeches={&props.get_speeches}
                 />
             }
@@ -92,6 +99,7 @@ pub fn charts(props: &ChartsProps) -> Html {
                 visible={props.show_speaker}
                 word={props.word.clone()}
                 show_counts={props.show_counts}
+                show_dispersion={props.show_dispersion}
                 get_speeches={&props.get_speeches}
             />
 
@@ -102,6 +110,7 @@ pub fn charts(props: &ChartsProps) -> Html {
                     visible={props.show_pop}
                     word={props.word.clone()}
                     show_counts={props.show_counts}
+                show_dispersion={props.show_dispersion}
                     get_speeches={&props.get_speeches}
                 />
             }
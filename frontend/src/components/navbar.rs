@@ -21,6 +21,11 @@ pub enum Route {
     #[at("/search")]
     Interface,
     
+    /// The route to the trending searches page.
+    
+    #[at("/trending")]
+    Trending,
+    
     /// The route to the about me page.
     
     #[at("/about")]
@@ -42,6 +47,7 @@ pub fn navbar() -> Html {
     let info = match location.path() {
         "/" => Route::Home,
         "/search" => Route::Interface,
+        "/trending" => Route::Trending,
         "/about" => Route::About,
         _ => Route::NotFound,
     };
@@ -84,6 +90,11 @@ pub fn navbar() -> Html {
                     <button class={if info == Route::Home {"button highlight"} else {"button"}}>{"info"}</button>
                 </Link<Route>>
             </div>
+            <div class="navbar-item">
+                <Link<Route> to={Route::Trending}>
+                    <button class={if info == Route::Trending {"button highlight"} else {"button"}}>{"trending"}</button>
+                </Link<Route>>
+            </div>
             <div class="navbar-item">
                 <Link<Route> to={Route::About}>
                     <button class={if info == Route::About {"button highlight"} else {"button"}}>{"about"}</button>
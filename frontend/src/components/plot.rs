@@ -1,14 +1,24 @@
-use common::models::{BreakdownType, DataRequest};
+use common::models::{BreakdownType, DataRequest, DispersionResponse, ErrorCode, ErrorResponse, StreamMessage};
 use yew::prelude::*;
 use wasm_bindgen_futures::spawn_local;
 use web_sys::{HtmlCanvasElement, CanvasRenderingContext2d};
 use wasm_bindgen::JsCast;
-use crate::util::{put, OverlaySelection};
+use crate::util::{cancel_request, download, download_url, error_response, get_demo, put, read_lines, OverlaySelection};
+use gloo::utils::document;
+use plotters::coord::Shift;
+use plotters::prelude::{DrawingArea, DrawingBackend, IntoDrawingArea, SVGBackend};
+use plotters_canvas::CanvasBackend;
+use common::export::{to_csv, to_json, ExportMeta};
 use crate::State;
 use std::rc::Rc;
 use std::cell::RefCell;
-use yew_hooks::prelude::use_window_size;
+use yew_hooks::prelude::{use_interval, use_window_size};
 use std::error::Error;
+use uuid::Uuid;
+
+/// The longest wait, in seconds, between automatic retries of a failed request.
+
+const MAX_RETRY_DELAY: u32 = 60;
 
 // todo replace with anyhow
 
@@ -32,12 +42,23 @@ pub trait Plottable<R>
     
     /// Sets the dynamic properties for this engine. These may need to be reset on rerender.
     
-    fn set_props(&mut self, window_width: f64, show_counts: bool, get_speeches: Callback<OverlaySelection>);
+    fn set_props(&mut self, window_width: f64, show_counts: bool, show_dispersion: bool, get_speeches: Callback<OverlaySelection>);
     
     /// Loads data into the engine.
     
     fn load_data(&mut self, data: Rc<Vec<R>>);
     
+    /// Returns the uri to request per-speaker dispersion data from, if this engine
+    /// is able to display it.
+    
+    fn dispersion_uri(&self) -> Option<String> {
+        None
+    }
+    
+    /// Loads per-speaker dispersion data into the engine.
+    
+    fn load_dispersion(&mut self, _data: Rc<Vec<DispersionResponse>>) {}
+    
     /// Whether the engine is empty of data.
     
     fn is_empty(&self) -> bool;
@@ -58,6 +79,12 @@ pub trait Plottable<R>
     
     fn redraw(&mut self, canvas: HtmlCanvasElement, inter_canvas: HtmlCanvasElement) -> Result<(), PlotError>;
     
+    /// Draws the plot on any plotters drawing area without touching the
+    /// interactive state, at the given reference width (for label sizes) and pixel
+    /// scale, with an optional caption. This is how images are exported.
+    
+    fn draw_on<DB: DrawingBackend>(&self, drawing_area: &DrawingArea<DB, Shift>, width: f64, scale: f64, caption: Option<&str>) -> Result<(), PlotError>;
+    
     /// Handle a mouse hover event. If the user is hovering over a bar/point, this
     /// means drawing an outline around it.
     
@@ -69,12 +96,98 @@ pub trait Plottable<R>
     fn clicked(&self, e: MouseEvent) -> Result<(), PlotError>;
 }
 
-/// A source of plot data - either a uri to request data from, or a json string.
+/// An image format a plot can be exported to.
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ImageFormat {
+    Png,
+    Svg,
+}
+
+/// Options for exporting a plot as an image. These are independent of the window
+/// width and device pixel ratio the plot is currently drawn at.
+
+#[derive(Clone, PartialEq)]
+pub struct ImageOptions {
+    
+    /// The width of the image in CSS pixels.
+    
+    pub width: u32,
+    
+    /// The height of the image in CSS pixels.
+    
+    pub height: u32,
+    
+    /// The resolution of the image. 96 dpi is one real pixel per CSS pixel. Ignored
+    /// for svg, which is resolution independent.
+    
+    pub dpi: u32,
+    
+    /// A caption drawn at the top of the image.
+    
+    pub caption: String,
+}
+
+/// Fills in the background of an exported image and draws the plot over it.
+
+fn draw_export<P, R, DB>(engine: &P, drawing_area: DrawingArea<DB, Shift>, options: &ImageOptions, scale: f64) -> Result<(), PlotError>
+    where
+        P: Plottable<R>,
+        R: PartialEq + std::fmt::Debug + 'static,
+        DB: DrawingBackend,
+{
+    drawing_area.fill(&plotting::BACKGROUND)?;
+    engine.draw_on(&drawing_area, options.width as f64, scale, Some(&options.caption))?;
+    drawing_area.present()?;
+    Ok(())
+}
+
+/// Renders the plot as an image and prompts the browser to download it.
+///
+/// Svg images go through plotters' svg backend. Png images are drawn by plotters
+/// onto an offscreen canvas at the requested resolution and encoded by the browser,
+/// since plotters' bitmap backend can't render text in wasm.
+
+pub fn export_image<P, R>(engine: &P, format: ImageFormat, options: &ImageOptions, filename: &str) -> Result<(), PlotError>
+    where
+        P: Plottable<R>,
+        R: PartialEq + std::fmt::Debug + 'static,
+{
+    match format {
+        ImageFormat::Svg => {
+            let mut svg = String::new();
+            {
+                let backend = SVGBackend::with_string(&mut svg, (options.width, options.height));
+                draw_export(engine, backend.into_drawing_area(), options, 1.0)?;
+            }
+            download(&format!("{}.svg", filename), "image/svg+xml", &svg).map_err(|_| PlotError)
+        },
+        ImageFormat::Png => {
+            let scale = options.dpi as f64 / 96.0;
+            let canvas = document()
+                .create_element("canvas")
+                .map_err(|_| PlotError)?
+                .dyn_into::<HtmlCanvasElement>()
+                .map_err(|_| PlotError)?;
+            canvas.set_width((options.width as f64 * scale) as u32);
+            canvas.set_height((options.height as f64 * scale) as u32);
+            
+            let backend = CanvasBackend::with_canvas_object(canvas.clone()).ok_or(PlotError)?;
+            draw_export(engine, backend.into_drawing_area(), options, scale)?;
+            
+            let url = canvas.to_data_url_with_type("image/png").map_err(|_| PlotError)?;
+            download_url(&format!("{}.png", filename), &url).map_err(|_| PlotError)
+        },
+    }
+}
+
+/// A source of plot data - either a uri to request data from, or the name of a
+/// demo dataset to fetch once from the backend.
 
 #[derive(Clone, PartialEq)]
 pub enum PlotSource {
     Uri(String),
-    Json(&'static str),
+    Demo(String),
 }
 
 /// Properties for the plot component.
@@ -87,7 +200,7 @@ pub struct PlotProps
     
     pub breakdown_type: BreakdownType,
     
-    /// The source of the plot data - a uri to request or a json string.
+    /// The source of the plot data - a uri to request or a demo dataset.
     
     pub source: PlotSource,
     
@@ -104,19 +217,24 @@ pub struct PlotProps
     
     pub show_counts: bool,
     
+    /// Whether we are showing the dispersion of per-speaker scores behind each
+    /// bar. Ignored by engines that can't display it.
+    
+    pub show_dispersion: bool,
+    
     /// A callback to bring up the speech overlay when a bar/point is clicked on
     /// the plot
     
     pub get_speeches: Callback<OverlaySelection>,
 }
 
-/// A fail state the plot can be in: one of generic, too many requests, or busy.
+/// A fail state the plot can be in: either a generic failure, or an error the
+/// backend explained.
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, PartialEq)]
 enum FailState {
     Generic,
-    TooMany,
-    Busy,
+    Api(ErrorResponse),
 }
 use FailState::*;
 
@@ -133,55 +251,73 @@ use PlotState::*;
 /// A flexible plot component that can request data and create a plot engine to
 /// render it.
 ///
+/// Data is streamed from the backend, which can be configured to count the search
+/// term over the corpus a chunk at a time, and the plot is redrawn with the partial
+/// data as each chunk arrives, with a progress bar until the last one does.
+///
 /// If the data request fails (or other errors occur) the plot enters a fail state
-/// and the page will need to be refreshed. If a status `204 No Content` is
+/// and offers a button to retry. If the backend was busy or rate limited the
+/// request, the plot also counts down to an automatic retry, waiting at least as
+/// long as the backend asked and backing off exponentially. If a status `204 No Content` is
 /// received, this represents a user-cancelled request, and the plot will silently
 /// remain loading, awaiting one of the two conditions that would have triggered
-/// a cancellation - destruction (the user leaving the page) or a new word.
+/// a cancellation - destruction (the user leaving the page) or a new word. Each
+/// data request has its own id, and the plot cancels its own stale requests when
+/// a new word is searched, or when it's hidden or destroyed while loading.
+///
+/// Once data is showing, the plot offers to download it as csv or json, along with
+/// the search term, data source, and generation time, or as a png or svg image at
+/// a chosen size and resolution.
 
 #[function_component(Plot)]
 pub fn plot<P, R>(props: &PlotProps) -> Html
     where
         P: Plottable<R> + 'static,
-        R: PartialEq + std::fmt::Debug + for<'a> serde::de::Deserialize<'a> + 'static
+        R: PartialEq + std::fmt::Debug + serde::Serialize + for<'a> serde::de::Deserialize<'a> + 'static
 {
-    let state = use_state(|| Showing);
-    let data_state: UseStateHandle<Option<Rc<Vec<R>>>> = use_state(||
-        if let PlotSource::Json(json) = &props.source {
-            if let Ok(data) = serde_json::from_str::<Vec<R>>(json) {
-                Some(Rc::from(data))
-            } else {
-                state.set(Failed(Generic));
-                None
-            }
-        }
-        else {None}
-    );
+    let state = use_state(|| if let PlotSource::Demo(_) = props.source {Loading} else {Showing});
+    let data_state: UseStateHandle<Option<Rc<Vec<R>>>> = use_state(|| None);
     let word_state = use_state(|| "".to_string());
+    let retry_attempt = use_state(|| 0u32);
+    let countdown: UseStateHandle<Option<u32>> = use_state(|| None);
+    let progress: UseStateHandle<Option<(usize, usize)>> = use_state(|| None);
+    let dispersion_state: UseStateHandle<Option<Rc<Vec<DispersionResponse>>>> = use_state(|| None);
+    let dispersion_word_state = use_state(|| "".to_string());
+    let in_flight: Rc<RefCell<Vec<(String, Uuid)>>> = use_mut_ref(Vec::new);
+    let image_size = use_state(|| (1200, 600));
+    let image_dpi = use_state(|| 150);
     let canvas = use_node_ref();
     let inter_canvas = use_node_ref();
     let window_width = use_window_size();
     let app_state = use_context::<State>();
     let engine: Rc<RefCell<P>> = use_mut_ref(|| Plottable::new(props.breakdown_type.clone()));
     
-    let heading = if let Ok(mut eng) = engine.try_borrow_mut() {
-        eng.set_props(window_width.0, props.show_counts, props.get_speeches.clone());
-        eng.get_heading()
+    let (heading, dispersion_uri) = if let Ok(mut eng) = engine.try_borrow_mut() {
+        eng.set_props(window_width.0, props.show_counts, props.show_dispersion, props.get_speeches.clone());
+        (eng.get_heading(), eng.dispersion_uri())
     } else {
         state.set(Failed(Generic));
-        "".to_string()
+        ("".to_string(), None)
     };
     
     {
         let engine = engine.clone();
         let app_state = app_state.clone();
         let visible = props.visible.clone();
+        let show_dispersion = props.show_dispersion;
         let data_state = data_state.clone();
+        let dispersion_state = dispersion_state.clone();
         let state = state.clone();
         let word = props.word.clone();
         let source = props.source.clone();
         let canvas = canvas.clone();
         let inter_canvas = inter_canvas.clone();
+        let word_state = word_state.clone();
+        let dispersion_word_state = dispersion_word_state.clone();
+        let in_flight = in_flight.clone();
+        let retry_attempt = retry_attempt.clone();
+        let countdown = countdown.clone();
+        let progress = progress.clone();
         use_effect(move || {
             if let Ok(mut eng) = engine.try_borrow_mut() {
                 if !eng.is_empty() {
@@ -191,32 +327,103 @@ pub fn plot<P, R>(props: &PlotProps) -> Html
                 }
             }
             
+            if let (PlotSource::Uri(_), Some(uri)) = (&source, dispersion_uri) {
+                if *dispersion_word_state != word && show_dispersion && visible {
+                    dispersion_word_state.set(word.clone());
+                    dispersion_state.set(None);
+                    let request = app_state.as_ref().map(|s| DataRequest::new(s.uuid, word.clone()));
+                    if let Some(request) = &request { replace_in_flight(&in_flight, &uri, request); }
+                    let in_flight = in_flight.clone();
+                    spawn_local(async move {
+                        let Some(dispersion_request) = request else { return };
+                        let request_id = dispersion_request.request_id;
+                        let resp = put(&format!("api/{}", uri), dispersion_request).await;
+                        let current = in_flight.borrow().iter().any(|(_, id)| *id == request_id);
+                        in_flight.borrow_mut().retain(|(_, id)| *id != request_id);
+                        if !current { return };
+                        let Ok(resp) = resp else { return };
+                        if resp.status() != 200 { return };
+                        let Ok(result) = resp.text().await else { return };
+                        let Ok(data) = serde_json::from_str::<Vec<DispersionResponse>>(&result) else { return };
+                        dispersion_state.set(Some(Rc::from(data)));
+                    });
+                }
+            }
+            
             if let PlotSource::Uri(uri) = source {
                 if *word_state != word && visible && *state != Failed(Generic) {
                     state.set(Loading);
+                    countdown.set(None);
+                    progress.set(None);
                     word_state.set(word.clone());
+                    let request = app_state.map(|s| DataRequest::new(s.uuid, word));
+                    if let Some(request) = &request { replace_in_flight(&in_flight, &uri, request); }
                     spawn_local(async move {
-                        let Some(app_state) = app_state
-                            else { state.set(Failed(Generic)); return };
-                        let breakdown_request = DataRequest { uuid: app_state.uuid, search: word };
-                        let Ok(resp) = put(&format!("api/{}", uri), breakdown_request).await
+                        let Some(breakdown_request) = request
                             else { state.set(Failed(Generic)); return };
+                        let request_id = breakdown_request.request_id;
+                        let resp = put(&format!("api/stream/{}", uri), breakdown_request).await;
+                        let Ok(resp) = resp else {
+                            in_flight.borrow_mut().retain(|(_, id)| *id != request_id);
+                            state.set(Failed(Generic));
+                            return
+                        };
                         
-                        state.set(match resp.status() {
+                        match resp.status() {
                             200 => {
-                                let Ok(result) = resp.text().await
-                                    else { state.set(Failed(Generic)); return };
-                                let Ok(data) = serde_json::from_str::<Vec<R>>(&result)
-                                    else { state.set(Failed(Generic)); return };
+                                let mut finished = false;
+                                let read = read_lines(&resp, |line| {
+                                    // drop anything still arriving for a request we've since cancelled
+                                    if !in_flight.borrow().iter().any(|(_, id)| *id == request_id) { return };
+                                    match serde_json::from_slice::<StreamMessage<R>>(line) {
+                                        Ok(StreamMessage::Chunk(chunk)) => {
+                                            finished = chunk.finished();
+                                            progress.set(if finished { None } else { Some((chunk.done, chunk.total)) });
+                                            data_state.set(Some(Rc::from(chunk.rows)));
+                                            if finished { retry_attempt.set(0); }
+                                            state.set(Showing);
+                                        },
+                                        Ok(StreamMessage::Error(error)) => {
+                                            finished = true;
+                                            progress.set(None);
+                                            state.set(api_failure(error, None, &retry_attempt, &countdown));
+                                        },
+                                        Err(_) => {
+                                            finished = true;
+                                            state.set(Failed(Generic));
+                                        },
+                                    }
+                                }).await;
                                 
-                                data_state.set(Some(Rc::from(data)));
-                                Showing
+                                // a stream that ends early was cancelled, so remain loading
+                                let current = in_flight.borrow().iter().any(|(_, id)| *id == request_id);
+                                in_flight.borrow_mut().retain(|(_, id)| *id != request_id);
+                                if current && read.is_err() {
+                                    state.set(Failed(Generic));
+                                } else if current && !finished {
+                                    progress.set(None);
+                                    state.set(Loading);
+                                }
+                            },
+                            204 => {
+                                let current = in_flight.borrow().iter().any(|(_, id)| *id == request_id);
+                                in_flight.borrow_mut().retain(|(_, id)| *id != request_id);
+                                if current {
+                                    state.set(Loading);
+                                }
+                            },
+                            _ => {
+                                let current = in_flight.borrow().iter().any(|(_, id)| *id == request_id);
+                                in_flight.borrow_mut().retain(|(_, id)| *id != request_id);
+                                if !current { return };
+                                let retry_after = resp.headers().get("retry-after")
+                                    .and_then(|v| v.parse::<u32>().ok());
+                                state.set(match error_response(resp).await {
+                                    Some(error) => api_failure(error, retry_after, &retry_attempt, &countdown),
+                                    None => Failed(Generic),
+                                });
                             },
-                            204 => Loading,
-                            429 => Failed(TooMany),
-                            503 => Failed(Busy),
-                            _ => Failed(Generic),
-                        });
+                        }
                     });
                 }
             };
@@ -225,6 +432,78 @@ pub fn plot<P, R>(props: &PlotProps) -> Html
         });
     }
     
+    // fetch the demo dataset once, if this is a demo plot
+    {
+        let data_state = data_state.clone();
+        let state = state.clone();
+        use_effect_with(props.source.clone(), move |source| {
+            if let PlotSource::Demo(name) = source.clone() {
+                spawn_local(async move {
+                    match get_demo::<Vec<R>>(&name).await {
+                        Ok(data) => {
+                            data_state.set(Some(Rc::from(data)));
+                            state.set(Showing);
+                        },
+                        Err(_) => state.set(Failed(Generic)),
+                    }
+                });
+            }
+        });
+    }
+    
+    // cancel any queries still loading when the plot is hidden or destroyed, and
+    // forget the word so they're requested again if the plot is shown. This runs
+    // after the fetch effect when the plot is shown, so only a plot that was
+    // visible cancels anything, or it would cancel the request it just started.
+    {
+        let in_flight = in_flight.clone();
+        let word_state = word_state.clone();
+        let dispersion_word_state = dispersion_word_state.clone();
+        let app_state = app_state.clone();
+        use_effect_with(props.visible, move |visible| {
+            let was_visible = *visible;
+            move || {
+                if !was_visible { return };
+                let requests = in_flight.borrow_mut().drain(..).collect::<Vec<(String, Uuid)>>();
+                if requests.is_empty() { return };
+                word_state.set("".to_string());
+                dispersion_word_state.set("".to_string());
+                let Some(app_state) = app_state else { return };
+                for (_, request_id) in requests {
+                    cancel_request(app_state.uuid, request_id);
+                }
+            }
+        });
+    }
+    
+    // count down to an automatic retry, which refetches by forgetting the word
+    {
+        let countdown = countdown.clone();
+        let word_state = word_state.clone();
+        let millis = if countdown.is_some() { 1000 } else { 0 };
+        use_interval(move || {
+            match *countdown {
+                Some(n) if n > 1 => countdown.set(Some(n - 1)),
+                Some(_) => {
+                    countdown.set(None);
+                    word_state.set("".to_string());
+                },
+                None => {},
+            }
+        }, millis);
+    }
+    
+    let retry = {
+        let countdown = countdown.clone();
+        let word_state = word_state.clone();
+        let state = state.clone();
+        Callback::from(move |_: MouseEvent| {
+            countdown.set(None);
+            state.set(Loading);
+            word_state.set("".to_string());
+        })
+    };
+    
     let onclick = {
         let engine = engine.clone();
         let state = state.clone();
@@ -237,6 +516,77 @@ pub fn plot<P, R>(props: &PlotProps) -> Html
         })
     };
     
+    let source = match &app_state {
+        Some(s) if s.provincial => "Ontario Legislature",
+        _ => "House of Commons",
+    };
+    let filename = format!("{}_{}", heading, props.word).replace(' ', "_");
+    
+    let export = |csv: bool| {
+        let data_state = data_state.clone();
+        let state = state.clone();
+        let word = props.word.clone();
+        let filename = filename.clone();
+        Callback::from(move |_: MouseEvent| {
+            let Some(data) = data_state.as_ref() else { return };
+            let meta = ExportMeta {
+                search: word.clone(),
+                source: source.to_string(),
+                generated: String::from(js_sys::Date::new_0().to_iso_string()),
+            };
+            let exported = if csv {
+                to_csv(&meta, data).map(|c| download(&format!("{}.csv", filename), "text/csv", &c))
+            } else {
+                to_json(&meta, data).map(|j| download(&format!("{}.json", filename), "application/json", &j))
+            };
+            if !matches!(exported, Ok(Ok(()))) {
+                state.set(Failed(Generic));
+            }
+        })
+    };
+    
+    let export_image_cb = |format: ImageFormat| {
+        let engine = engine.clone();
+        let state = state.clone();
+        let image_size = image_size.clone();
+        let image_dpi = image_dpi.clone();
+        let filename = filename.clone();
+        let caption = format!("\"{}\" - {} - {}", props.word, heading, source);
+        Callback::from(move |_: MouseEvent| {
+            let options = ImageOptions {
+                width: image_size.0,
+                height: image_size.1,
+                dpi: *image_dpi,
+                caption: caption.clone(),
+            };
+            let Ok(eng) = engine.try_borrow() else { state.set(Failed(Generic)); return };
+            export_image::<P, R>(&eng, format, &options, &filename).unwrap_or_else(|_| { state.set(Failed(Generic)); });
+        })
+    };
+    
+    let on_image_size = {
+        let image_size = image_size.clone();
+        Callback::from(move |e: Event| {
+            if let Some(select) = e.target_dyn_into::<web_sys::HtmlSelectElement>() {
+                let mut dims = select.value().split('x').filter_map(|d| d.parse::<u32>().ok()).collect::<Vec<u32>>();
+                if let (Some(h), Some(w)) = (dims.pop(), dims.pop()) {
+                    image_size.set((w, h));
+                }
+            }
+        })
+    };
+    
+    let on_image_dpi = {
+        let image_dpi = image_dpi.clone();
+        Callback::from(move |e: Event| {
+            if let Some(select) = e.target_dyn_into::<web_sys::HtmlSelectElement>() {
+                if let Ok(dpi) = select.value().parse::<u32>() {
+                    image_dpi.set(dpi);
+                }
+            }
+        })
+    };
+    
     let onmousemove = {
         let engine = engine.clone();
         let state = state.clone();
@@ -257,20 +607,30 @@ pub fn plot<P, R>(props: &PlotProps) -> Html
     let mut canvas_style = "display: none".to_string();
     let mut inter_canvas_style = "display: none".to_string();
     let mut message_style = "";
-    let mut message = "no results found";
+    let mut message = "no results found".to_string();
     let mut loader_style = "display: none";
+    let mut export_style = "display: none";
+    let mut retry_style = "display: none";
+    let (progress_style, progress_done, progress_total) = match *progress {
+        Some((done, total)) if *state == Showing => ("", done, total),
+        _ => ("display: none", 0, 1),
+    };
     
     match data_state.as_ref() {
         None => {},
         Some(d) => {
             if let Ok(mut eng) = engine.try_borrow_mut() {
                 eng.load_data(d.clone());
+                if let Some(dd) = dispersion_state.as_ref() {
+                    eng.load_dispersion(dd.clone());
+                }
                 if eng.is_empty() { message_style = "display: initial"; }
                 else {
                     let width = eng.get_width();
                     let height = eng.get_height();
                     
                     let canvas_opacity = if *state == Loading {"0.25"} else {"1"};
+                    if progress.is_none() { export_style = ""; }
                     canvas_style = format!("opacity: {}; width: {}px; height: {}px", canvas_opacity, width, height);
                     inter_canvas_style = format!("width: {}px; height: {}px", width, height);
                     if let (Some(canvas), Some(inter_canvas)) = (canvas.clone().cast(), inter_canvas.clone().cast()) {
@@ -288,15 +648,25 @@ pub fn plot<P, R>(props: &PlotProps) -> Html
         Loading => {
             loader_style = "display: flex";
         },
-        Failed(e) => {
+        Failed(ref e) => {
             canvas_style = "display: none".to_string();
             inter_canvas_style = "display: none".to_string();
+            export_style = "display: none";
             message_style = "display: initial";
             message = match e {
-                Generic => "an error occurred - please try refreshing",
-                TooMany => "too many requests - please slow down",
-                Busy => "servers busy - please try again later",
+                Generic => "an error occurred".to_string(),
+                Api(error) => error.message.clone(),
+            };
+            if let Some(n) = *countdown {
+                message = format!("{} - retrying in {}s", message, n);
+            }
+            let retryable = match e {
+                Generic => true,
+                Api(error) => matches!(error.code, ErrorCode::Internal | ErrorCode::Busy | ErrorCode::RateLimited | ErrorCode::TooManyQueries | ErrorCode::Timeout),
             };
+            if retryable && matches!(props.source, PlotSource::Uri(_)) {
+                retry_style = "";
+            }
         },
     }
         
@@ -306,8 +676,27 @@ pub fn plot<P, R>(props: &PlotProps) -> Html
                 <div class="loader-wrapper" style={loader_style}>
                     <div class="loader"/>
                 </div>
-                <h2 class="plot-heading">{heading}</h2>
+                <h2 class="plot-heading">{heading.clone()}</h2>
+                <div class="plot-export" style={export_style}>
+                    <button class="button" onclick={export(true)}>{"csv"}</button>
+                    <button class="button" onclick={export(false)}>{"json"}</button>
+                    <select class="plot-export-select" onchange={on_image_size}>
+                        <option value="1200x600" selected=true>{"1200×600"}</option>
+                        <option value="1600x800">{"1600×800"}</option>
+                        <option value="2400x1200">{"2400×1200"}</option>
+                        <option value="1080x1080">{"1080×1080"}</option>
+                    </select>
+                    <select class="plot-export-select" onchange={on_image_dpi}>
+                        <option value="96">{"96 dpi"}</option>
+                        <option value="150" selected=true>{"150 dpi"}</option>
+                        <option value="300">{"300 dpi"}</option>
+                    </select>
+                    <button class="button" onclick={export_image_cb(ImageFormat::Png)}>{"png"}</button>
+                    <button class="button" onclick={export_image_cb(ImageFormat::Svg)}>{"svg"}</button>
+                </div>
+                <progress class="plot-progress" style={progress_style} value={progress_done.to_string()} max={progress_total.to_string()} />
                 <h3 class="plot-message" style={message_style}>{message}</h3>
+                <button class="button plot-retry" style={retry_style} onclick={retry}>{"retry"}</button>
                 <canvas class="inter-canvas" style={inter_canvas_style} {onclick} {onmousemove} ref={inter_canvas} />
                 <canvas class="canvas" style={canvas_style} ref={canvas} />
             </div>
@@ -318,6 +707,37 @@ pub fn plot<P, R>(props: &PlotProps) -> Html
     }
 }
 
+/// Returns the fail state for an error the backend explained. If the backend was
+/// busy or rate limited the request, this also starts the countdown to an automatic
+/// retry, waiting at least as long as the backend asked and backing off
+/// exponentially.
+
+fn api_failure(error: ErrorResponse, retry_after: Option<u32>, retry_attempt: &UseStateHandle<u32>, countdown: &UseStateHandle<Option<u32>>) -> PlotState {
+    if matches!(error.code, ErrorCode::Busy | ErrorCode::RateLimited | ErrorCode::TooManyQueries) {
+        let backoff = 2u32.saturating_pow(**retry_attempt).min(MAX_RETRY_DELAY);
+        let wait = retry_after.or(error.retry_after.map(|s| s as u32)).unwrap_or(0);
+        countdown.set(Some(wait.max(backoff)));
+        retry_attempt.set(**retry_attempt + 1);
+    }
+    Failed(Api(error))
+}
+
+/// Cancels the requests to the given uri that are still loading, and records a new
+/// request to it as loading in their place. Requests for the plot's other uri are
+/// left alone.
+
+fn replace_in_flight(in_flight: &RefCell<Vec<(String, Uuid)>>, uri: &str, request: &DataRequest) {
+    in_flight.borrow_mut().retain(|(u, request_id)| {
+        if u == uri {
+            cancel_request(request.uuid, *request_id);
+            false
+        } else {
+            true
+        }
+    });
+    in_flight.borrow_mut().push((uri.to_string(), request.request_id));
+}
+
 /// Returns the CanvasRenderingContext2d object for the given HtmlCanvasElement
 
 pub fn canvas_context(canvas: &HtmlCanvasElement) -> Option<CanvasRenderingContext2d> {
@@ -0,0 +1,73 @@
+use crate::components::navbar::Route;
+use crate::pages::interface_page::SearchQuery;
+use common::models::{TrendingPeriod, TrendingResponse, TrendingTerm};
+use gloo_net::http::Request;
+use wasm_bindgen_futures::spawn_local;
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+/// The most popular searches suggested.
+
+const SUGGESTIONS: usize = 8;
+
+/// Fetches the most searched terms over a period, or None if they couldn't be
+/// fetched.
+
+pub async fn fetch_trending(period: TrendingPeriod) -> Option<TrendingResponse> {
+    let uri = format!("/api/trending?period={}", period);
+    let resp = Request::get(&uri).send().await.ok()?;
+    if resp.status() != 200 {
+        return None;
+    }
+    let resp_text = resp.text().await.ok()?;
+    serde_json::from_str::<TrendingResponse>(&resp_text).ok()
+}
+
+/// Returns a link to a search for the term in the interface.
+
+pub fn search_link(term: &str) -> Html {
+    html! {
+        <Link<Route, SearchQuery> to={Route::Interface} query={Some(SearchQuery::search(term))}>
+            {term}
+        </Link<Route, SearchQuery>>
+    }
+}
+
+/// A list of the most popular searches of the last week, each linking to the
+/// search interface.
+///
+/// Nothing is shown if there haven't been any searches, or the analytics couldn't
+/// be fetched, since the suggestions are only a nicety.
+
+#[function_component(PopularSearches)]
+pub fn popular_searches() -> Html {
+    let terms = use_state(Vec::<TrendingTerm>::new);
+
+    {
+        let terms = terms.clone();
+        use_effect_with((), move |_| {
+            spawn_local(async move {
+                if let Some(trending) = fetch_trending(TrendingPeriod::Week).await {
+                    terms.set(trending.terms.into_iter().take(SUGGESTIONS).collect());
+                }
+            });
+        });
+    }
+
+    if terms.is_empty() {
+        return html! {};
+    }
+
+    html! {
+        <p class="popular-searches">
+            {"Not sure what to search? Here's what people have been looking up this week: "}
+            { for terms.iter().enumerate().map(|(i, t)| html! {
+                <>
+                    if i > 0 { {", "} }
+                    { search_link(&t.term) }
+                </>
+            }) }
+            {"."}
+        </p>
+    }
+}
@@ -1,42 +1,17 @@
 use plotters::prelude::*;
+use plotters::coord::Shift;
 use plotters_canvas::CanvasBackend;
 use yew::prelude::*;
 use web_sys::HtmlCanvasElement;
 use gloo::utils::window;
 use common::models::PopulationResponse;
 use crate::util::OverlaySelection;
-use std::cmp::{min, max};
+use plotting::population::{point_size, PopulationChart};
+use plotting::{Chart, PointMapping};
 use crate::components::plot::{Plottable, canvas_context, PlotError};
 use common::models::BreakdownType;
 use std::rc::Rc;
 
-/// A population graph coordinate mapping for interactivity.
-///
-/// The struct holds x and y coordinates of a data point, and the id and name of
-/// the riding represented by that data point.
-
-#[derive(Default, Clone)]
-struct CoordMapping {
-    x: i32,
-    y: i32,
-    id: i32,
-    name: String,
-}
-
-/// A population density data point.
-///
-/// This struct is used once we've made the conversion from the raw backend
-/// response, which only contains the population and area.
-
-struct PopDensity {
-    pub id: i32,
-    pub name: String,
-    pub pop_density: f64,
-    pub colour: String,
-    pub count: i64,
-    pub score: f32,
-}
-
 /// The main engine for the population graph.
 
 pub struct PopulationEngine {
@@ -64,7 +39,7 @@ pub struct PopulationEngine {
     
     /// The coordinate mappings from x/y to id and name.
     
-    coord_mappings: Vec<CoordMapping>,
+    coord_mappings: Vec<PointMapping>,
     
     /// A callback to bring up the speech overlay for a clicked dot.
     
@@ -76,12 +51,12 @@ impl PopulationEngine {
     /// Returns a sane point display size, relative to the dpr.
     
     fn point_size(&self) -> i32 {
-        (5.0 * self.dpr) as i32
+        point_size(self.dpr)
     }
     
     /// Returns a coordinate mapping that matches the mouse's position, if any.
     
-    fn mouse_mapping(&self, e: MouseEvent) -> CoordMapping {
+    fn mouse_mapping(&self, e: MouseEvent) -> PointMapping {
         let ps = self.point_size();
         let x = (e.offset_x() as f64 * self.dpr) as i32;
         let y = (e.offset_y() as f64 * self.dpr) as i32;
@@ -91,7 +66,13 @@ impl PopulationEngine {
             }
         }
         // todo use an option here
-        CoordMapping::default()
+        PointMapping::default()
+    }
+    
+    /// Returns a borrowed chart of the engine's data, ready to draw.
+    
+    fn chart(&self) -> PopulationChart<'_> {
+        PopulationChart { data: &self.data, show_counts: self.show_counts }
     }
 }
 
@@ -113,7 +94,7 @@ impl Plottable<PopulationResponse> for PopulationEngine {
     
     /// Sets the dynamic properties for this engine. These may need to be reset on rerender.
     
-    fn set_props(&mut self, window_width: f64, show_counts: bool, get_speeches: Callback<OverlaySelection>) {
+    fn set_props(&mut self, window_width: f64, show_counts: bool, _show_dispersion: bool, get_speeches: Callback<OverlaySelection>) {
         self.window_width = window_width;
         self.show_counts = show_counts;
         self.get_speeches = Some(get_speeches);
@@ -134,13 +115,13 @@ impl Plottable<PopulationResponse> for PopulationEngine {
     /// Returns a sane calculated width for the graph.
     
     fn get_width(&self) -> u32 {
-        min(max(900, (self.window_width * 0.97) as u32), 1800)
+        self.chart().width(self.window_width)
     }
     
     /// Returns a sane calculated height for the graph.
     
     fn get_height(&self) -> u32 {
-        500
+        plotting::HEIGHT
     }
     
     /// Returns a heading for the graph.
@@ -161,85 +142,17 @@ impl Plottable<PopulationResponse> for PopulationEngine {
         inter_canvas.set_width(canvas_width as u32);
 
         let backend = CanvasBackend::with_canvas_object(canvas).ok_or(PlotError)?;
-        let drawing_area = backend.into_drawing_area();
-        let mut label_size = (canvas_width.sqrt() / 2.5) as u32;
-        
-        let data = self.data.as_ref().iter().map(|r| { PopDensity {
-            id: r.id,
-            name: r.name.clone(),
-            pop_density: r.population as f64 / r.area,
-            colour: r.colour.clone(),
-            score: r.score,
-            count: r.count,
-        }}).collect::<Vec<PopDensity>>();
-        
-        let x_max = data.iter().map(|r| { r.pop_density }).max_by(|a, b| {a.total_cmp(b)}).ok_or(PlotError)?;
-        let y_max = data.iter().map(|r| { r.score }).max_by(|a, b| {a.total_cmp(b)}).ok_or(PlotError)?;
-        let c_max = data.iter().map(|r| { r.count }).max_by(|a, b| a.cmp(b)).ok_or(PlotError)?;
-
-        let bold_line = hex::decode("97948f")?;
-        let light_line = hex::decode("67635c")?;
-
-        label_size = max((label_size as f64 * (1.0 + (self.dpr * 0.1))) as u32, (8.0 * self.dpr) as u32);
-        let desc_style = TextStyle::from(("sans-serif", (16.0 * self.dpr) as u32).into_font()).color(&WHITE);
-        self.coord_mappings = vec![];
-        
-        if !self.show_counts {
-            let mut chart = ChartBuilder::on(&drawing_area)
-                .x_label_area_size((50.0 * self.dpr) as u32)
-                .y_label_area_size((70.0 * self.dpr) as u32)
-                .build_cartesian_2d((0.02..x_max).log_scale(), 0.0..y_max)?;
-            
-            chart.configure_mesh()
-                .x_desc("population per square kilometer") 
-                .x_label_style(TextStyle::from(("sans-serif", label_size).into_font()).color(&WHITE))   
-                .y_label_style(TextStyle::from(("sans-serif", label_size).into_font()).color(&WHITE))
-                .axis_desc_style(desc_style)
-                .bold_line_style(RGBColor(bold_line[0], bold_line[1], bold_line[2]))
-                .light_line_style(RGBColor(light_line[0], light_line[1], light_line[2]))
-                .y_desc("word count per 100,000")
-                .draw()?;
-                
-            chart.draw_series(data.iter().map(|r| {
-                let rgb = hex::decode(r.colour.clone())?;
-                Ok(Circle::new((r.pop_density, r.score), self.point_size(), RGBColor(rgb[0], rgb[1], rgb[2]).filled()))
-            }).collect::<Result<Vec<Circle<(f64, f32), i32>>, PlotError>>()?)?;
-            
-            for r in data.iter() {
-                let p = chart.backend_coord(&(r.pop_density, r.score));
-                self.coord_mappings.push(CoordMapping { x: p.0, y: p.1, id: r.id, name: r.name.clone() });
-            };
-        }
-        else {
-            let mut chart= ChartBuilder::on(&drawing_area)
-                .x_label_area_size((50.0 * self.dpr) as u32)
-                .y_label_area_size((70.0 * self.dpr) as u32)
-                .build_cartesian_2d((0.02..x_max).log_scale(), 0..c_max)?;
-            
-            chart.configure_mesh()
-                .x_desc("population per square kilometer") 
-                .x_label_style(TextStyle::from(("sans-serif", label_size).into_font()).color(&WHITE))   
-                .y_label_style(TextStyle::from(("sans-serif", label_size).into_font()).color(&WHITE))
-                .axis_desc_style(desc_style)
-                .bold_line_style(RGBColor(bold_line[0], bold_line[1], bold_line[2]))
-                .light_line_style(RGBColor(light_line[0], light_line[1], light_line[2]))
-                .y_desc("total word count")
-                .y_label_formatter(&|v| { format!("{}", *v as u32) })
-                .draw()?;
-                
-            chart.draw_series(data.iter().map(|r| {
-                let rgb = hex::decode(r.colour.clone())?;
-                Ok(Circle::new((r.pop_density, r.count), self.point_size(), RGBColor(rgb[0], rgb[1], rgb[2]).filled()))
-            }).collect::<Result<Vec<Circle<(f64, i64), i32>>, PlotError>>()?)?;
-            
-            for r in data.iter() {
-                let p = chart.backend_coord(&(r.pop_density, r.count));
-                self.coord_mappings.push(CoordMapping { x: p.0, y: p.1, id: r.id, name: r.name.clone() });
-            };
-        }
+        self.coord_mappings = self.chart().draw(&backend.into_drawing_area(), self.get_width() as f64, self.dpr, None)?;
         Ok(())
     }
     
+    /// Draws the graph on any plotters drawing area, at the given reference width
+    /// and pixel scale, with an optional caption.
+    
+    fn draw_on<DB: DrawingBackend>(&self, drawing_area: &DrawingArea<DB, Shift>, width: f64, scale: f64, caption: Option<&str>) -> Result<(), PlotError> {
+        Ok(self.chart().draw_on(drawing_area, width, scale, caption)?)
+    }
+    
     /// Handles a mouse hover event. If the user is hovering over a point, this
     /// means drawing an outline around it.
     
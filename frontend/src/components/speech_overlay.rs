@@ -1,4 +1,4 @@
-use common::models::{BreakdownType, DataRequest, CancelRequest, SpeechResponse};
+use common::models::{BreakdownType, DataRequest, SpeechResponse};
 use crate::components::speech_box::SpeechBox;
 use crate::pages::error_page::error_page;
 use crate::State;
@@ -30,14 +30,17 @@ pub struct SpeechOverlayProps {
 
 /// A speech overlay component, displaying a requested set of speeches.
 ///
-/// This overlay mostly hides the page below.
+/// This overlay mostly hides the page below. If it's closed while speeches are
+/// still loading, the speech query is cancelled.
 
 #[function_component(SpeechOverlay)]
 pub fn speech_overlay(props: &SpeechOverlayProps) -> Html {
     let data = use_state(|| None);
     let failed = use_state(|| false);
+    let error = use_state(|| None::<String>);
     let app_state = use_context::<State>();
     let selection_state = use_state(|| OverlaySelection { breakdown_type: BreakdownType::Party, id: 0, heading: String::from("")} ); // todo use default?
+    let loading = use_mut_ref(|| None::<uuid::Uuid>);
 
     {
         let data = data.clone();
@@ -46,25 +49,44 @@ pub fn speech_overlay(props: &SpeechOverlayProps) -> Html {
         let word = props.word.clone();
         let visible = props.visible;
         let failed = failed.clone();
+        let error = error.clone();
+        let selection_state = selection_state.clone();
+        let loading = loading.clone();
         use_effect(move || {
             if visible && (*selection_state) != selection {
                 data.set(None);
+                error.set(None);
                 selection_state.set(selection.clone());
                 spawn_local(async move {
                     let Some(state) = app_state
                         else { failed.set(true); return };
                     
-                    let cancel_request = CancelRequest { uuid: state.uuid };
-                    let Ok(_) = put("/api/cancel/speeches", cancel_request).await
-                        else { failed.set(true); return };
+                    // cancel the previous selection's request if it's still loading
+                    if let Some(request_id) = loading.take() {
+                        cancel_request(state.uuid, request_id);
+                    }
                     
                     let uri = format!("/api/speeches/{}/{}", selection.breakdown_type, selection.id);
-                    let speech_request = DataRequest { uuid: state.uuid, search: word };
-                    let Ok(resp) = put(&uri, speech_request).await
+                    let speech_request = DataRequest::new(state.uuid, word);
+                    let request_id = speech_request.request_id;
+                    *loading.borrow_mut() = Some(request_id);
+                    let resp = put(&uri, speech_request).await;
+                    if *loading.borrow() == Some(request_id) {
+                        *loading.borrow_mut() = None;
+                    }
+                    let Ok(resp) = resp
                         else { failed.set(true); return };
                             
-                    if resp.status() == 204 {
-                        return;
+                    match resp.status() {
+                        200 => {},
+                        204 => return,
+                        _ => {
+                            match error_response(resp).await {
+                                Some(e) => error.set(Some(e.message)),
+                                None => failed.set(true),
+                            }
+                            return;
+                        }
                     }
                     
                     let Ok(result) = resp.text().await else { failed.set(true); return };
@@ -76,6 +98,21 @@ pub fn speech_overlay(props: &SpeechOverlayProps) -> Html {
         });
     }
     
+    // cancel the speech query if the overlay is hidden or destroyed while it's
+    // loading, and forget the selection so it's requested again if reopened
+    {
+        let app_state = app_state.clone();
+        let selection_state = selection_state.clone();
+        use_effect_with(props.visible, move |_| {
+            move || {
+                let Some(request_id) = loading.take() else { return };
+                selection_state.set(OverlaySelection { breakdown_type: BreakdownType::Party, id: 0, heading: String::from("") });
+                let Some(state) = app_state else { return };
+                cancel_request(state.uuid, request_id);
+            }
+        });
+    }
+    
     if !props.visible { return html! { <div style="display: none" /> }};
     
     let loading = html! { <div class="loader-speech" /> };
@@ -88,6 +125,9 @@ pub fn speech_overlay(props: &SpeechOverlayProps) -> Html {
                     {
                         if let Some(state) = app_state {
                             match (*failed, data.as_ref(), state.speakers) {
+                                (false, None, _) if error.is_some() => {
+                                    html! { <h2 class="speech-overlay-message">{(*error).clone()}</h2> }
+                                },
                                 (false, None, _) => { loading },
                                 (false, _, Ok(None)) => { loading },
                                 (false, Some(data), Ok(Some(speakers))) => {
@@ -14,6 +14,7 @@ use pages::about_me_page::AboutMePage;
 use pages::error_page::error_page;
 use pages::interface_page::InterfacePage;
 use pages::info_page::InfoPage;
+use pages::trending_page::TrendingPage;
 use std::collections::HashMap;
 use uuid::Uuid;
 use wasm_bindgen_futures::spawn_local;
@@ -56,6 +57,9 @@ fn switch(routes: Route) -> Html {
         Route::Interface => html! { 
             <InterfacePage />
         },
+        Route::Trending => html! {
+            <TrendingPage />
+        },
         Route::About => html! {
             <AboutMePage />
         },
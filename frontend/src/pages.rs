@@ -2,4 +2,4 @@ pub mod about_me_page;
 pub mod error_page;
 pub mod interface_page;
 pub mod info_page;
-pub mod info_page_data;
+pub mod trending_page;
@@ -1,22 +1,31 @@
 use crate::components::breakdown_engine::BreakdownEngine;
 use crate::components::plot::{Plot, PlotSource};
+use crate::components::popular_searches::PopularSearches;
 use crate::components::population_engine::PopulationEngine;
 use crate::components::speech_box::SpeechBox;
 use crate::components::speech_overlay::SpeechOverlay;
-use crate::pages::error_page::error_page;
-use crate::pages::info_page_data::*;
-use crate::util::OverlaySelection;
+use crate::util::{get_demo, OverlaySelection};
 use crate::State;
-use common::models::{BreakdownResponse, BreakdownType, PopulationResponse};
+use common::models::{
+    BreakdownResponse, BreakdownType, DemoChart, DemoWalkthrough, PopulationResponse,
+    SpeechResponse,
+};
 use gloo::utils::body;
+use wasm_bindgen_futures::spawn_local;
 use yew::prelude::*;
 
 /// An info page explaining the tool, with embedded demo charts.
+///
+/// The demo charts and speech are listed in the data source's `walkthrough` demo,
+/// along with what the page says about each of them, and each is shown once it's
+/// fetched from the backend. Any the walkthrough doesn't list are left out, along
+/// with the text that explains them, so every legislature shares the same page.
 
 #[function_component(InfoPage)]
 pub fn info_page() -> Html {
     let app_state = use_context::<State>();
-    let failed = use_state(|| false);
+    let walkthrough = use_state(|| None::<Option<DemoWalkthrough>>);
+    let speech_data = use_state(|| None::<SpeechResponse>);
 
     let show_charts = use_state(|| false);
     let speech_overlay_word = use_state(|| String::from(""));
@@ -27,17 +36,23 @@ pub fn info_page() -> Html {
         heading: String::from(""),
     });
 
-    if let Some(state) = app_state {
-        if state.provincial {
-            // todo finish this and move it back to its own page
-            return html! {
-                <div class="info">
-                    <h2>{"What is this tool?"}</h2>
-                    <p>{"This tool is a word search that lets you compare how different categories of provincial MMPs use language in the Ontario legislature. Click the search button at the top to try it out!"}</p>
-                    <p>{"The rest of this info page stuck in development as I am currently working on the federal version of this tool hosted "}<a href="https://housewords.chunkerbunker.cc">{"here"}</a>{"."}</p>
-                </div>
-            };
-        }
+    {
+        let walkthrough = walkthrough.clone();
+        let speech_data = speech_data.clone();
+        use_effect_with((), move |_| {
+            spawn_local(async move {
+                let Ok(demos) = get_demo::<DemoWalkthrough>("walkthrough").await else {
+                    walkthrough.set(Some(None));
+                    return;
+                };
+                if let Some(speech) = &demos.speech {
+                    if let Ok(speech) = get_demo::<SpeechResponse>(&speech.demo).await {
+                        speech_data.set(Some(speech));
+                    }
+                }
+                walkthrough.set(Some(Some(demos)));
+            });
+        });
     }
 
     let get_speeches = |w: String| {
@@ -60,8 +75,6 @@ pub fn info_page() -> Html {
         })
     };
 
-    let speech_data = speech_data();
-
     let toggle_charts = |toggle| {
         let show_charts = show_charts.clone();
         Callback::from(move |_| {
@@ -73,121 +86,148 @@ pub fn info_page() -> Html {
         })
     };
 
-    html! {
-        if !*failed {
-            <div class="info">
-                <h2>{"What is this tool?"}</h2>
+    let demo_chart = |chart: &DemoChart, breakdown_type: BreakdownType, show_counts: bool| {
+        html! {
+            <div class="info-chart">
+                <Plot<BreakdownEngine, BreakdownResponse>
+                    breakdown_type={breakdown_type}
+                    source={PlotSource::Demo(chart.demo.clone())}
+                    visible={true}
+                    word={""}
+                    show_counts={show_counts}
+                    show_dispersion={false}
+                    get_speeches={get_speeches(chart.term.clone())}
+                />
+            </div>
+        }
+    };
 
-                <p>{"This tool is a word search that lets you use basic charts to compare how different categories of Canadian MPs have used language in the House of Commons during the 44th parliament. You can access it by clicking the search button at the top of this page."}</p>
+    let paragraphs = |text: &[String]| -> Html {
+        text.iter()
+            .map(|paragraph| html! { <p>{paragraph.clone()}</p> })
+            .collect()
+    };
 
-                <p>{"To use the search interface, type in a word or phrase into the box labeled \"search term\" and hit the submit button. For example, here's what will pop up with the default settings if you enter \"pipeline\":"}</p>
-                <div class="info-chart">
-                    <Plot<BreakdownEngine, BreakdownResponse>
-                        breakdown_type={BreakdownType::Party}
-                        source={PlotSource::Json(pipeline_party_data())}
-                        visible={true}
-                        word={""}
-                        show_counts={false}
-                        get_speeches={get_speeches("pipeline".to_owned())}
-                    />
-                </div>
-                <p>{"As you can see, the Green party uses this word a lot. They have only two members in the house, but those two said \"pipeline\" more than 200 times. Their bar on the chart above is much taller than other parties because it's measuring the number of pipeline mentions for every 100,000 words they spoke in total."}</p>
+    let speaker_name = |id: i32| {
+        app_state
+            .as_ref()
+            .and_then(|state| state.speakers.as_ref().ok().cloned().flatten())
+            .and_then(|speakers| speakers.get(&id).cloned())
+            .map(|speaker| format!("{} {}", speaker.first_name, speaker.last_name))
+            .unwrap_or_default()
+    };
 
-                <p>{"Members of the Conservative Party said \"pipeline\" more than 650 times, in fact, but this is a much smaller number of mentions in proportion to their 120 seats, as well as the extra speaking time they get as the official opposition. If you're wondering where I got that 650 number, you can check \"total counts\" to the right of the search box and see a second set of bars on the chart to the right of the original ones. These correspond with an axis on the right side of the chart that measures the total times each party said the word you searched:"}</p>
-                <div class="info-chart">
-                    <Plot<BreakdownEngine, BreakdownResponse>
-                        breakdown_type={BreakdownType::Party}
-                        source={PlotSource::Json(pipeline_party_data())}
-                        visible={true}
-                        word={""}
-                        show_counts={true}
-                        get_speeches={get_speeches("pipeline".to_owned())}
-                    />
+    let provincial = app_state.as_ref().is_some_and(|state| state.provincial);
+    let (members, legislature) = if provincial {
+        ("MPPs", "the Legislative Assembly of Ontario")
+    } else {
+        (
+            "Canadian MPs",
+            "the House of Commons during the 44th parliament",
+        )
+    };
+
+    let demos = (*walkthrough).clone().flatten().unwrap_or_default();
+    let chart_option = |id: &str, label: &str, shown: bool| {
+        html! {
+            if shown {
+                <div>
+                    <label for={id.to_owned()}> {label.to_owned()}</label>
+                    <input type="checkbox" id={id.to_owned()}/>
                 </div>
-                <p>{"So what kind of meaning can we draw from this? Not much, and we have to be careful. There are a lot of limitations that come with using raw word counts to make assumptions about the kinds of things a group of people are talking about."}</p>
+            }
+        }
+    };
 
-                <p>{"Let's go back to our example. If you've ever followed Canadian federal politics, you might guess that members and supporters of the Green Party and the Conservative Party would have very different opinions on investment in and construction of oil and gas pipelines. So would it be fair to say that the Green Party spends a lot more of their (limited) time criticizing these pipelines than the Conservative Party spends defending them?"}</p>
+    html! {
+        <div class="info">
+            <h2>{"What is this tool?"}</h2>
+
+            <p>{format!("This tool is a word search that lets you use basic charts to compare how different categories of {members} have used language in {legislature}. You can access it by clicking the search button at the top of this page.")}</p>
 
-                <p>{"Or are they even talking about oil and gas? Perhaps these MPs are talking about city pipelines, data pipelines, or using the term metaphorically."}</p>
+            <PopularSearches />
 
-                <p>{"The best way to find out is to click on the bar directly! This will bring up a list of speeches, in chronological order, where a member of that party used the term you searched. Try it on the graph above. The first speech to come up if you click the Green party's bar looks like this:"}</p>
+            if *walkthrough == Some(None) {
+                <p>{"The examples for this legislature are still being written. In the meantime, the federal version of this page, hosted "}<a href="https://housewords.chunkerbunker.cc">{"here"}</a>{", walks through each chart with real searches."}</p>
+            }
 
+            <p>{"To use the search interface, type in a word or phrase into the box labeled \"search term\" and hit the submit button. With the default settings, a chart will pop up showing how often the members of each party used your search term."}</p>
+
+            if let Some(party) = &demos.party {
+                { paragraphs(&party.intro) }
+                <p>{format!("For example, here's what comes up if you enter \"{}\":", party.term)}</p>
+                { demo_chart(party, BreakdownType::Party, false) }
+                { paragraphs(&party.caption) }
+            }
+
+            <p>{"The height of each bar isn't the number of times a party used the term, but the number of times for every 100,000 words its members spoke in total. This way a party with only a few seats, or one that gets less time to speak, can be compared fairly with the larger ones. To see the raw totals, check \"total counts\" to the right of the search box, and a second set of bars will appear next to the first, measured on the axis on the right side of the chart."}</p>
+
+            if let Some(party) = &demos.party {
+                { demo_chart(party, BreakdownType::Party, true) }
+            }
+
+            <p>{"So what kind of meaning can we draw from this? Not much, and we have to be careful. There are a lot of limitations that come with using raw word counts to make assumptions about the kinds of things a group of people are talking about. The same word can be used in very different contexts, by members who agree or disagree about the subject, or in a completely unrelated sense."}</p>
+
+            if let Some(speech) = &demos.speech {
+                { paragraphs(&speech.intro) }
+            }
+
+            <p>{"The best way to find out is to click on a bar directly! This will bring up a list of speeches, in chronological order, where a member of that party used the term you searched."}</p>
+
+            if let (Some(speech), Some(demo)) = ((*speech_data).clone(), &demos.speech) {
+                <p>{format!("Here's one of the speeches that comes up for \"{}\":", demo.term)}</p>
                 <div class="info-speech">
                     <SpeechBox
-                        name={"Elizabeth May"}
-                        start={speech_data.start}
-                        end={speech_data.end}
-                        link={speech_data.link}
-                        text={speech_data.text}
-                        word={"pipeline".to_owned()}
+                        name={speaker_name(speech.speaker)}
+                        start={speech.start}
+                        end={speech.end}
+                        link={speech.link}
+                        text={speech.text}
+                        word={demo.term.clone()}
                     />
                 </div>
+            }
 
-                <p>{"If you'd like to read the original Hansard House Debates, or watch the accompanying videos, click the date at the top of the speech."}</p>
-
-                <p>{"In this case the results do line up with our first assumption. Most of the times this raw word \"pipeline\" is used, the members are talking about oil and gas pipelines. The Green Party spends their time criticizing them from a number of angles, whereas the array of Conservative voices who mention them do so occasionally, and in positive contexts."}</p>
-
-                <p>{"Sometimes the search results may not be what you expect, and that's why I encourage you to explore the actual text of the speeches. If you're the kind of Ontarian that doesn't pay much attention to Quebec politics, here is a chance to check out what members of the Bloc might be saying about pipelines."}</p>
-
-                <p>{"I mention the Bloc because this brings up another limitation: the data presented here is based on transcripts and "}<em>{"translations"}</em>{" provided by the House of Commons. That means that almost every word spoken in French has been translated into English, and you have to be careful not to make assumptions based on the specific kind of language that a French speaker is using when they don't have full control over the translated result."}</p>
-
-                <h2>{"More Charts"}</h2>
-
-                <p>{"There are a few other charts included that you can add to the view by clicking the button that looks like this in the interface:"}</p>
-                <div onmouseleave={&toggle_charts(false)}>
-                    <button class="button" onclick={&toggle_charts(true)} >{"charts"}</button>
-                    <div class="chart-dropdown" style={if *show_charts {"display: block"} else {"display: none"}} >
-                        <div>
-                            <label for="show_party"> {"party"}</label>
-                            <input type="checkbox" id="show_party"/>
-                        </div>
-                        <div>
-                            <label for="show_gender"> {"gender"}</label>
-                            <input type="checkbox" id="show_gender"/>
-                        </div>
-                        <div>
-                            <label for="show_province"> {"province"}</label>
-                            <input type="checkbox" id="show_province"/>
-                        </div>
-                        <div>
-                            <label for="show_speaker"> {"speaker"}</label>
-                            <input type="checkbox" id="show_speaker"/>
-                        </div>
-                        <div>
-                            <label for="show_pop"> {"pop density"}</label>
-                            <input type="checkbox" id="show_pop"/>
-                        </div>
-                    </div>
-                </div>
+            <p>{"If you'd like to read the original Hansard transcript, click the date at the top of a speech. Sometimes the search results may not be what you expect, and that's why I encourage you to explore the actual text of the speeches."}</p>
 
-                <p>{"The gender breakdown is just like the party breakdown, but shows the words spoken by men, women, and one individual who identifies as Two-Spirit. Here's what the graph looks like for \"mental health\":"}</p>
-                <div class="info-chart">
-                    <Plot<BreakdownEngine, BreakdownResponse>
-                        breakdown_type={BreakdownType::Gender}
-                        source={PlotSource::Json(mental_gender_data())}
-                        visible={true}
-                        word={""}
-                        show_counts={true}
-                        get_speeches={get_speeches("mental health".to_owned())}
-                    />
+            if let Some(speech) = &demos.speech {
+                { paragraphs(&speech.caption) }
+            }
+
+            <h2>{"More Charts"}</h2>
+
+            <p>{"There are a few other charts included that you can add to the view by clicking the button that looks like this in the interface:"}</p>
+            <div onmouseleave={&toggle_charts(false)}>
+                <button class="button" onclick={&toggle_charts(true)} >{"charts"}</button>
+                <div class="chart-dropdown" style={if *show_charts {"display: block"} else {"display: none"}} >
+                    { chart_option("show_party", "party", true) }
+                    { chart_option("show_gender", "gender", demos.gender.is_some()) }
+                    { chart_option("show_province", "province", demos.province.is_some()) }
+                    { chart_option("show_class", "class", demos.class.is_some()) }
+                    { chart_option("show_speaker", "speaker", demos.speaker.is_some()) }
+                    { chart_option("show_pop", "pop density", demos.population.is_some()) }
                 </div>
-                <p>{"I have chosen to place that one MP - Blake Desjarlais of the NDP - in his own bucket to respect Two-Spirit as a distinct gender identity, but doing my due diligence would involve contacting him to get his preference. So long as he is in a distinct category, the results you may try to read are going to be statistically skewed in many ways, and cannot be said to represent anything about all Two-Spirit people."}</p>
+            </div>
+
+            if let Some(gender) = &demos.gender {
+                <p>{"The gender breakdown is just like the party breakdown, but shows the words spoken by members of each gender."}</p>
+                { paragraphs(&gender.intro) }
+                <p>{format!("Here's what the graph looks like for \"{}\":", gender.term)}</p>
+                { demo_chart(gender, BreakdownType::Gender, true) }
+                { paragraphs(&gender.caption) }
+            }
 
+            if let Some(province) = &demos.province {
                 <hr class="divider" />
 
-                <p>{"The breakdown by province is fairly self-explanatory. Here's what it looks like when you search \"trump\":"}</p>
-                <div class="info-chart">
-                    <Plot<BreakdownEngine, BreakdownResponse>
-                        breakdown_type={BreakdownType::Province}
-                        source={PlotSource::Json(trump_province_data())}
-                        visible={true}
-                        word={""}
-                        show_counts={false}
-                        get_speeches={get_speeches("trump".to_owned())}
-                    />
-                </div>
-                <p>{"In this case, I might be curious why Manitoba tops the list and Saskatchewan comes closer to the bottom."}</p>
+                <p>{"The breakdown by province is fairly self-explanatory."}</p>
+                { paragraphs(&province.intro) }
+                <p>{format!("Here's what it looks like when you search \"{}\":", province.term)}</p>
+                { demo_chart(province, BreakdownType::Province, false) }
+                { paragraphs(&province.caption) }
+            }
 
+            if let Some(class) = &demos.class {
                 <hr class="divider" />
 
                 <p>{"The breakdown by class is more complicated. Class is a loaded concept, and I am using it in a very crude sense here to refer to one's material stake in the economy. The implication is that a people who only work for wages, people who are building equity through homeownership, people who own productive capital, and people who collect rent are all disparate groups, with differing perspectives and interests in the direction of the economy and public policy. "}</p>
@@ -203,62 +243,51 @@ pub fn info_page() -> Html {
                     </ul>
                 </p>
 
-                <p>{"Here's an example for the search term \"economy\":"}</p>
-                <div class="info-chart">
-                    <Plot<BreakdownEngine, BreakdownResponse>
-                        breakdown_type={BreakdownType::Class}
-                        source={PlotSource::Json(class_economy_data())}
-                        visible={true}
-                        word={""}
-                        show_counts={false}
-                        get_speeches={get_speeches("economy".to_owned())}
-                    />
-                </div>
-                <p>{"Why might MPs with investments and rental properties be more likely to use the very word \"economy\" itself?"}</p>
+                { paragraphs(&class.intro) }
+                <p>{format!("Here's an example for the search term \"{}\":", class.term)}</p>
+                { demo_chart(class, BreakdownType::Class, false) }
+                { paragraphs(&class.caption) }
+            }
 
+            if let Some(speaker) = &demos.speaker {
                 <hr class="divider" />
 
-                <p>{"The speaker breakdown shows the same kind of information but for individual speakers. It's limited to the top ten results and looks like this with the example search \"pharmacare\":"}</p>
-                <div class="info-chart">
-                    <Plot<BreakdownEngine, BreakdownResponse>
-                        breakdown_type={BreakdownType::Speaker}
-                        source={PlotSource::Json(pharma_speaker_data())}
-                        visible={true}
-                        word={""}
-                        show_counts={true}
-                        get_speeches={get_speeches("pharmacare".to_owned())}
-                    />
-                </div>
-                <p>{"The bars are coloured according to their party affiliation, and the results are limited to the top ten hits. This can be a very useful way of finding particular MPs who have taken on particular issues either as representatives of their parties, because of constituent demand, or because of private interest in the subject."}</p>
+                <p>{"The speaker breakdown shows the same kind of information but for individual members. It's limited to the top ten results, and the bars are coloured according to each member's party. This can be a useful way of finding the members who have taken on particular issues, either as representatives of their parties, because of constituent demand, or because of a personal interest in the subject."}</p>
+                { paragraphs(&speaker.intro) }
+                <p>{format!("Here's what it looks like for \"{}\":", speaker.term)}</p>
+                { demo_chart(speaker, BreakdownType::Speaker, true) }
+                { paragraphs(&speaker.caption) }
+            }
 
+            if let Some(population) = &demos.population {
                 <hr class="divider" />
 
-                <p>{"The population density scatterplot shows you how the word usage correlates with how dense an MPs riding is. Large remote ridings appear on the left, followed by rural and then increasingly urban ridings toward the right. Toronto Center, the most population dense riding, will always appear on the far right of the chart."}</p>
-
-                <p>{"Organizing this information by population density doesn't usually show anything statistically meaningful, but you can use it to check out any outliers. One word that gives an interesting result is \"gaza\":"}</p>
+                <p>{"The population density scatterplot shows you how the word usage correlates with how dense a member's riding is. Large remote ridings appear on the left, followed by rural and then increasingly urban ridings toward the right."}</p>
+                { paragraphs(&population.intro) }
+                <p>{format!("Organizing this information by population density doesn't usually show anything statistically meaningful, but you can use it to check out any outliers. One word that gives an interesting result is \"{}\":", population.term)}</p>
                 <div class="info-chart">
                     <Plot<PopulationEngine, PopulationResponse>
                         breakdown_type={BreakdownType::Speaker}
-                        source={PlotSource::Json(gaza_pop_data())}
+                        source={PlotSource::Demo(population.demo.clone())}
                         visible={true}
                         word={""}
                         show_counts={false}
-                        get_speeches={get_speeches("gaza".to_owned())}
+                        show_dispersion={false}
+                        get_speeches={get_speeches(population.term.clone())}
                     />
                 </div>
-                <p>{"Note that almost all of the people who have actually spoken about Gaza in the house come from urban ridings. Of the few exceptions, way at the left side of the graph, none are Conservative. Each of these dots can be clicked to bring up the speeches made by the MP in that riding."}</p>
-
-                if (*selection).id != 0 {
-                    <SpeechOverlay
-                        selection={(*selection).clone()}
-                        word={(*speech_overlay_word).clone()}
-                        visible={*speech_overlay_visible}
-                        hide={hide_speech_overlay}
-                    />
-                }
-            </div>
-        } else {
-            { error_page() }
-        }
+                { paragraphs(&population.caption) }
+                <p>{"Each of these dots can be clicked to bring up the speeches made by the member for that riding."}</p>
+            }
+
+            if selection.id != 0 {
+                <SpeechOverlay
+                    selection={(*selection).clone()}
+                    word={(*speech_overlay_word).clone()}
+                    visible={*speech_overlay_visible}
+                    hide={hide_speech_overlay}
+                />
+            }
+        </div>
     }
 }
@@ -1,440 +0,0 @@
-use common::models::SpeechResponse;
-use time::macros::{date, time};
-use time::PrimitiveDateTime;
-
-// todo move this all to the backend
-
-/// Returns demo party breakdown data in json format for the word "pipeline".
-
-pub fn pipeline_party_data() -> &'static str {
-    r#"[
-        {"id":1,"name":"Liberal","colour":"EA6D6A","count":175,"score":2.192533},
-        {"id":2,"name":"Conservative","colour":"6495ED","count":631,"score":6.753006},
-        {"id":3,"name":"Bloc","colour":"87CEFA","count":222,"score":6.9208617},
-        {"id":4,"name":"NDP","colour":"F4A460","count":274,"score":8.032308},
-        {"id":5,"name":"Green","colour":"98CA66","count":207,"score":48.23286},
-        {"id":6,"name":"Independent","colour":"DCDCDC","count":3,"score":2.5524747}
-    ]"#
-}
-
-/// Returns a demo speech in json format.
-
-pub fn speech_data() -> SpeechResponse {
-    SpeechResponse {
-        speaker: 218,
-        text: "Mr. Speaker, I am honoured to present a petition regarding the Trans Mountain pipeline. It is of critical concern to the petitioners that the Trans Mountain pipeline expansion not take place. They point out that a diluted bitumen spill would devastate local ecosystems and economies throughout British Columbia, but particularly in the coastal zone and anywhere along the 800 water bodies, tributaries and rivers the pipeline would cross. The petitioners call on the government to cancel any plans to put public money into, or to approve any expansion of, the Trans Mountain pipeline.".to_string(),
-        link: "https://www.ourcommons.ca/documentviewer/en/44-1/house/sitting-3/hansard".to_string(),
-        start: PrimitiveDateTime::new(date!(2021-11-24), time!(15:30)),
-        end: PrimitiveDateTime::new(date!(2021-11-24), time!(15:30)),
-    }
-}
-
-/// Returns demo gender breakdown data in json format for the phrase "mental
-/// health".
-
-pub fn mental_gender_data() -> &'static str {
-    r#"[
-        {"id":1,"name":"female","colour":"F4F462","count":1900,"score":28.98253},
-        {"id":2,"name":"male","colour":"ABF462","count":3468,"score":19.467318},
-        {"id":3,"name":"two-spirit","colour":"AB62F4","count":22,"score":16.175756}
-    ]"#
-}
-
-/// Returns demo province breakdown data in json format for the word "trump".
-
-pub fn trump_province_data() -> &'static str {
-    r#"[
-        {"id":1,"name":"Alberta","colour":"6495ED","count":54,"score":1.7877634},
-        {"id":2,"name":"British Columbia","colour":"F4A460","count":100,"score":2.822971},
-        {"id":3,"name":"Manitoba","colour":"98CA66","count":98,"score":4.46076},
-        {"id":4,"name":"New Brunswick","colour":"E996E9","count":10,"score":2.9614272},
-        {"id":7,"name":"Nova Scotia","colour":"BF96E9","count":16,"score":1.8575255},
-        {"id":8,"name":"Nunavut","colour":"96ABE9","count":2,"score":1.7631865},
-        {"id":9,"name":"Ontario","colour":"EA6D6A","count":266,"score":3.496633},
-        {"id":11,"name":"Quebec","colour":"87CEFA","count":155,"score":2.714894},
-        {"id":12,"name":"Saskatchewan","colour":"FFF169","count":11,"score":1.2284546}
-    ]"#
-}
-
-/// Returns demo class data in json format for the word "economy".
-
-pub fn class_economy_data() -> &'static str {
-    r#"[
-        {"id":1,"name":"None","colour":"FFF169","count":329,"score":30.371454708},
-        {"id":2,"name":"Homeowner","colour":"96ABE9","count":1908,"score":33.825636468},
-        {"id":3,"name":"Landlord","colour":"EA6D6A","count":2112,"score":51.872734848},
-        {"id":4,"name":"Investor","colour":"98CA66","count":2479,"score":55.370639083},
-        {"id":5,"name":"All","colour":"BF96E9","count":4311,"score":51.288475698}
-    ]"#
-}
-
-/// Returns demo speaker breakdown data for the word "pharmacare".
-
-pub fn pharma_speaker_data() -> &'static str {
-    r#"[
-        {"id":242,"name":"Yasir Naqvi","colour":"EA6D6A","count":53,"score":95.425},
-        {"id":164,"name":"Peter Julian","colour":"F4A460","count":258,"score":82.8277},
-        {"id":79,"name":"Leila Dance","colour":"F4A460","count":2,"score":67.40816},
-        {"id":171,"name":"Robert Kitchen","colour":"6495ED","count":22,"score":63.096912},
-        {"id":315,"name":"Joanne Thompson","colour":"EA6D6A","count":10,"score":62.75494},
-        {"id":259,"name":"Marcus Powlowski","colour":"EA6D6A","count":12,"score":57.034218},
-        {"id":82,"name":"Don Davies","colour":"F4A460","count":55,"score":46.690887},
-        {"id":146,"name":"Lisa Hepfner","colour":"EA6D6A","count":15,"score":44.9991},
-        {"id":46,"name":"Alexandre Boulerice","colour":"F4A460","count":71,"score":44.773483},
-        {"id":293,"name":"Sonia Sidhu","colour":"EA6D6A","count":9,"score":41.430744}
-    ]"#
-}
-
-/// Returns demo population density data for the word "gaza".
-
-pub fn gaza_pop_data() -> &'static str {
-    r#"[
-        {"id":1,"name":"Edmonton Manning","population":132224,"area":157.08,"colour":"6495ED","count":0,"score":0.0},
-        {"id":2,"name":"Parry Sound-Muskoka","population":104494,"area":12237.99,"colour":"6495ED","count":0,"score":0.0},
-        {"id":3,"name":"Central Okanagan—Similkameen—Nicola","population":122340,"area":15965.94,"colour":"6495ED","count":0,"score":0.0},
-        {"id":4,"name":"Cloverdale—Langley City","population":130665,"area":59.42,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":5,"name":"Mississauga Centre","population":127377,"area":23.11,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":6,"name":"Brampton Centre","population":104557,"area":43.62,"colour":"EA6D6A","count":8,"score":93.512566},
-        {"id":7,"name":"Niagara West","population":96946,"area":988.1,"colour":"6495ED","count":0,"score":0.0},
-        {"id":8,"name":"Oakville","population":122322,"area":78.35,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":9,"name":"Scarborough—Rouge Park","population":102254,"area":53.23,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":10,"name":"Timmins-James Bay","population":80785,"area":241782.68,"colour":"F4A460","count":22,"score":12.217945},
-        {"id":11,"name":"North Okanagan—Shuswap","population":136520,"area":16158.36,"colour":"6495ED","count":0,"score":0.0},
-        {"id":12,"name":"Madawaska—Restigouche","population":60184,"area":11681.73,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":13,"name":"Nepean","population":132769,"area":171.54,"colour":"EA6D6A","count":11,"score":13.038428},
-        {"id":14,"name":"Churchill—Keewatinook Aski","population":81258,"area":420975.01,"colour":"F4A460","count":8,"score":9.924204},
-        {"id":15,"name":"Fredericton","population":87436,"area":1499.26,"colour":"EA6D6A","count":1,"score":1.7755681},
-        {"id":16,"name":"Skeena—Bulkley Valley","population":89689,"area":325624.67,"colour":"F4A460","count":0,"score":0.0},
-        {"id":17,"name":"Niagara Centre","population":119809,"area":301.73,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":18,"name":"Steveston—Richmond East","population":102230,"area":85.87,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":19,"name":"Etobicoke Centre","population":118483,"area":37.05,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":20,"name":"Niagara Falls","population":146404,"area":507.84,"colour":"6495ED","count":0,"score":0.0},
-        {"id":21,"name":"Foothills","population":115118,"area":20751.67,"colour":"6495ED","count":0,"score":0.0},
-        {"id":22,"name":"Leeds—Grenville—Thousand Islands and Rideau Lakes","population":104070,"area":3355.61,"colour":"6495ED","count":0,"score":0.0},
-        {"id":23,"name":"Nanaimo—Ladysmith","population":134509,"area":1726.78,"colour":"F4A460","count":2,"score":2.180193},
-        {"id":24,"name":"Pierre-Boucher—Les Patriotes—Verchères","population":103020,"area":685.16,"colour":"87CEFA","count":0,"score":0.0},
-        {"id":25,"name":"Sydney—Victoria","population":72361,"area":4282.09,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":26,"name":"La Pointe-de-l'Île","population":110486,"area":41.44,"colour":"87CEFA","count":0,"score":0.0},
-        {"id":27,"name":"Burnaby North—Seymour","population":108794,"area":114.56,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":28,"name":"Outremont","population":103620,"area":11.94,"colour":"EA6D6A","count":3,"score":3.8357284},
-        {"id":29,"name":"Toronto—St. Paul's","population":116953,"area":13.12,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":31,"name":"Portage—Lisgar","population":100417,"area":12559.18,"colour":"6495ED","count":0,"score":0.0},
-        {"id":32,"name":"Montarville","population":100515,"area":150.28,"colour":"87CEFA","count":43,"score":92.74437},
-        {"id":33,"name":"Mégantic—L'Érable","population":88894,"area":5930.4,"colour":"6495ED","count":2,"score":1.1857332},
-        {"id":34,"name":"Abitibi—Baie-James—Nunavik—Eeyou","population":89087,"area":731401.36,"colour":"87CEFA","count":0,"score":0.0},
-        {"id":35,"name":"Selkirk—Interlake—Eastman","population":101373,"area":19210.88,"colour":"6495ED","count":4,"score":3.4425778},
-        {"id":36,"name":"Compton—Stanstead","population":111088,"area":4554.83,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":37,"name":"St. Catharines","population":114782,"area":55.14,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":38,"name":"Elmwood—Transcona","population":101691,"area":49.53,"colour":"F4A460","count":0,"score":0.0},
-        {"id":39,"name":"Scarborough Southwest","population":111994,"area":28.12,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":40,"name":"Beloeil—Chambly","population":125359,"area":377.68,"colour":"87CEFA","count":22,"score":27.173243},
-        {"id":41,"name":"Rimouski-Neigette—Témiscouata—Les Basques","population":85556,"area":7724.44,"colour":"87CEFA","count":0,"score":0.0},
-        {"id":42,"name":"North Island—Powell River","population":111825,"area":53540.47,"colour":"F4A460","count":3,"score":2.396932},
-        {"id":43,"name":"Carlton Trail—Eagle Creek","population":83395,"area":28076.91,"colour":"6495ED","count":0,"score":0.0},
-        {"id":44,"name":"Kings—Hants","population":87744,"area":4117.46,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":45,"name":"Edmonton Centre","population":109125,"area":45.48,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":46,"name":"Rosemont—La Petite-Patrie","population":112909,"area":10.71,"colour":"F4A460","count":28,"score":17.657148},
-        {"id":47,"name":"Kitchener South—Hespeler","population":119851,"area":104.24,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":48,"name":"Tobique—Mactaquac","population":69344,"area":14529.46,"colour":"6495ED","count":0,"score":0.0},
-        {"id":49,"name":"Barrie—Innisfil","population":120378,"area":330.74,"colour":"6495ED","count":0,"score":0.0},
-        {"id":50,"name":"Sherbrooke","population":119038,"area":99.31,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":51,"name":"Brantford—Brant","population":140139,"area":829.18,"colour":"6495ED","count":0,"score":0.0},
-        {"id":52,"name":"Lac-Saint-Jean","population":103886,"area":55366.57,"colour":"87CEFA","count":27,"score":25.017605},
-        {"id":53,"name":"Red Deer—Lacombe","population":131575,"area":6199.55,"colour":"6495ED","count":0,"score":0.0},
-        {"id":54,"name":"South Okanagan—West Kootenay","population":123487,"area":17688.91,"colour":"F4A460","count":2,"score":1.81102},
-        {"id":55,"name":"Kamloops—Thompson—Cariboo","population":135492,"area":37623.21,"colour":"6495ED","count":0,"score":0.0},
-        {"id":56,"name":"Winnipeg South Centre","population":94803,"area":44.64,"colour":"EA6D6A","count":2,"score":8.263097},
-        {"id":57,"name":"Winnipeg South Centre","population":94803,"area":44.64,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":58,"name":"Oshawa","population":131067,"area":60.67,"colour":"6495ED","count":0,"score":0.0},
-        {"id":59,"name":"Charlottetown","population":38809,"area":44.27,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":60,"name":"Thérèse-De Blainville","population":106013,"area":74.72,"colour":"87CEFA","count":1,"score":1.2527561},
-        {"id":61,"name":"Waterloo","population":127235,"area":72.67,"colour":"EA6D6A","count":3,"score":6.468584},
-        {"id":62,"name":"Calgary Skyview","population":159642,"area":121.47,"colour":"EA6D6A","count":4,"score":17.117426},
-        {"id":63,"name":"Simcoe North","population":120656,"area":1752.09,"colour":"6495ED","count":0,"score":0.0},
-        {"id":64,"name":"Saint-Maurice—Champlain","population":111997,"area":34186.99,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":65,"name":"Drummond","population":107967,"area":1599.1,"colour":"87CEFA","count":0,"score":0.0},
-        {"id":66,"name":"Pontiac","population":129781,"area":26974.07,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":67,"name":"Scarborough North","population":94717,"area":30.37,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":68,"name":"Markham—Unionville","population":128308,"area":83.78,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":69,"name":"Wellington—Halton Hills","population":127873,"area":1492.58,"colour":"6495ED","count":18,"score":20.889668},
-        {"id":70,"name":"Hamilton East—Stoney Creek","population":112028,"area":67.27,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":71,"name":"Victoria","population":123482,"area":40.26,"colour":"F4A460","count":6,"score":5.9473066},
-        {"id":72,"name":"St. Albert—Edmonton","population":130822,"area":104.4,"colour":"6495ED","count":0,"score":0.0},
-        {"id":73,"name":"Acadie—Bathurst","population":77594,"area":5052.81,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":74,"name":"Don Valley East","population":95039,"area":22.78,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":75,"name":"West Nova","population":83571,"area":8869.18,"colour":"6495ED","count":1,"score":0.65330017},
-        {"id":76,"name":"Toronto—Danforth","population":105472,"area":20.39,"colour":"EA6D6A","count":13,"score":19.53037},
-        {"id":77,"name":"Pitt Meadows—Maple Ridge","population":110416,"area":2042.72,"colour":"6495ED","count":0,"score":0.0},
-        {"id":78,"name":"Oakville North—Burlington","population":148936,"area":87.23,"colour":"EA6D6A","count":38,"score":51.03754},
-        {"id":79,"name":"Elmwood—Transcona","population":101691,"area":49.53,"colour":"F4A460","count":0,"score":0.0},
-        {"id":80,"name":"Kildonan—St. Paul","population":91480,"area":169.71,"colour":"6495ED","count":0,"score":0.0},
-        {"id":81,"name":"York—Simcoe","population":124458,"area":790.74,"colour":"6495ED","count":0,"score":0.0},
-        {"id":82,"name":"Vancouver Kingsway","population":108054,"area":15.3,"colour":"F4A460","count":0,"score":0.0},
-        {"id":83,"name":"Salaberry—Suroît","population":119351,"area":2140.09,"colour":"87CEFA","count":0,"score":0.0},
-        {"id":84,"name":"Louis-Saint-Laurent","population":123123,"area":138.01,"colour":"6495ED","count":0,"score":0.0},
-        {"id":85,"name":"Beauport—Côte-de-Beaupré—Île d'Orléans—Charlevoix","population":95736,"area":11115.81,"colour":"87CEFA","count":0,"score":0.0},
-        {"id":86,"name":"Rivière-des-Mille-Îles","population":105589,"area":114.95,"colour":"87CEFA","count":0,"score":0.0},
-        {"id":87,"name":"Edmonton Griesbach","population":111699,"area":45.95,"colour":"F4A460","count":21,"score":15.4404955},
-        {"id":88,"name":"Surrey—Newton","population":122264,"area":30.03,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":89,"name":"Dorval—Lachine—LaSalle","population":119395,"area":49.7,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":90,"name":"Halifax West","population":111944,"area":227.31,"colour":"EA6D6A","count":5,"score":12.311631},
-        {"id":91,"name":"Cariboo—Prince George","population":111226,"area":82820.22,"colour":"6495ED","count":0,"score":0.0},
-        {"id":92,"name":"Don Valley North","population":113663,"area":24.34,"colour":"DCDCDC","count":0,"score":0.0},
-        {"id":93,"name":"Simcoe—Grey","population":151784,"area":1856.92,"colour":"6495ED","count":0,"score":0.0},
-        {"id":94,"name":"Red Deer—Mountain View","population":120739,"area":7645.5,"colour":"6495ED","count":0,"score":0.0},
-        {"id":95,"name":"Glengarry—Prescott—Russell","population":116463,"area":2909.26,"colour":"EA6D6A","count":3,"score":6.7816534},
-        {"id":96,"name":"Bourassa","population":105637,"area":13.54,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":97,"name":"Québec","population":98772,"area":34.49,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":98,"name":"Winnipeg South","population":113370,"area":101.27,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":99,"name":"Stormont—Dundas—South Glengarry","population":104493,"area":2665.45,"colour":"6495ED","count":0,"score":0.0},
-        {"id":100,"name":"Etobicoke North","population":116003,"area":47.77,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":101,"name":"Davenport","population":105946,"area":12.08,"colour":"EA6D6A","count":1,"score":1.2925408},
-        {"id":102,"name":"Willowdale","population":118218,"area":19.75,"colour":"EA6D6A","count":11,"score":65.04642},
-        {"id":103,"name":"Laval—Les Îles","population":111784,"area":46.09,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":104,"name":"Cumberland—Colchester","population":82014,"area":7903.27,"colour":"6495ED","count":0,"score":0.0},
-        {"id":105,"name":"Chatham-Kent—Leamington","population":113654,"area":2039.68,"colour":"6495ED","count":0,"score":0.0},
-        {"id":106,"name":"Beaches—East York","population":109359,"area":16.74,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":107,"name":"Battlefords—Lloydminster","population":70918,"area":29158.72,"colour":"6495ED","count":0,"score":0.0},
-        {"id":108,"name":"Provencher","population":109445,"area":18030.62,"colour":"6495ED","count":0,"score":0.0},
-        {"id":109,"name":"Abbotsford","population":110331,"area":175.19,"colour":"6495ED","count":0,"score":0.0},
-        {"id":110,"name":"Hull—Aylmer","population":110881,"area":62.45,"colour":"EA6D6A","count":1,"score":0.6140696},
-        {"id":111,"name":"Peterborough—Kawartha","population":125478,"area":2955.93,"colour":"6495ED","count":0,"score":0.0},
-        {"id":112,"name":"Halifax","population":107010,"area":214.58,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":113,"name":"South Surrey—White Rock","population":119672,"area":144.29,"colour":"6495ED","count":0,"score":0.0},
-        {"id":114,"name":"Dartmouth—Cole Harbour","population":96165,"area":89.99,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":115,"name":"Mississauga East—Cooksville","population":116346,"area":32.02,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":116,"name":"Ottawa—Vanier","population":118806,"area":39.19,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":117,"name":"Rivière-du-Nord","population":122654,"area":355.35,"colour":"87CEFA","count":4,"score":5.037339},
-        {"id":118,"name":"London North Centre","population":138255,"area":58.86,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":119,"name":"Central Nova","population":73188,"area":9280.95,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":120,"name":"University—Rosedale","population":106216,"area":13.57,"colour":"EA6D6A","count":2,"score":1.7614162},
-        {"id":121,"name":"Vancouver Centre","population":126995,"area":11.2,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":122,"name":"Mississauga—Malton","population":116908,"area":97.11,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":123,"name":"Notre-Dame-de-Grâce—Westmount","population":105601,"area":16.04,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":124,"name":"Renfrew—Nipissing—Pembroke","population":107420,"area":11359.2,"colour":"6495ED","count":2,"score":2.4912806},
-        {"id":125,"name":"Notre-Dame-de-Grâce—Westmount","population":105601,"area":16.04,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":126,"name":"Mirabel","population":132930,"area":836.53,"colour":"87CEFA","count":0,"score":0.0},
-        {"id":127,"name":"Esquimalt—Saanich—Sooke","population":128644,"area":328.51,"colour":"F4A460","count":0,"score":0.0},
-        {"id":128,"name":"Laurentides—Labelle","population":123796,"area":17587.83,"colour":"87CEFA","count":0,"score":0.0},
-        {"id":129,"name":"Winnipeg Centre","population":87499,"area":28.3,"colour":"F4A460","count":28,"score":25.105577},
-        {"id":130,"name":"Montmagny—L'Islet—Kamouraska—Rivière-du-Loup","population":96724,"area":7310.1,"colour":"6495ED","count":0,"score":0.0},
-        {"id":131,"name":"Sherwood Park—Fort Saskatchewan","population":126313,"area":1226.98,"colour":"6495ED","count":13,"score":2.9650578},
-        {"id":132,"name":"Kingston and the Islands","population":126106,"area":358.43,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":133,"name":"Manicouagan","population":88525,"area":234442.27,"colour":"87CEFA","count":0,"score":0.0},
-        {"id":134,"name":"Sarnia—Lambton","population":107077,"area":1475.85,"colour":"6495ED","count":1,"score":0.9979044},
-        {"id":135,"name":"Portneuf—Jacques-Cartier","population":123243,"area":7157.43,"colour":"6495ED","count":1,"score":2.1501677},
-        {"id":136,"name":"Fort McMurray—Cold Lake","population":110163,"area":142870.37,"colour":"6495ED","count":0,"score":0.0},
-        {"id":137,"name":"Burlington","population":125435,"area":75.96,"colour":"EA6D6A","count":1,"score":1.1690164},
-        {"id":138,"name":"Lévis—Lotbinière","population":118608,"area":2065.42,"colour":"6495ED","count":0,"score":0.0},
-        {"id":139,"name":"Kelowna—Lake Country","population":136290,"area":1565.53,"colour":"6495ED","count":0,"score":0.0},
-        {"id":140,"name":"Hamilton Centre","population":106439,"area":30.24,"colour":"F4A460","count":15,"score":24.123125},
-        {"id":141,"name":"Laurier—Sainte-Marie","population":116932,"area":10.36,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":142,"name":"Thunder Bay—Superior North","population":83325,"area":76105.89,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":143,"name":"Calgary Forest Lawn","population":107939,"area":52.74,"colour":"6495ED","count":0,"score":0.0},
-        {"id":144,"name":"Yukon","population":40232,"area":472345.44,"colour":"EA6D6A","count":4,"score":9.81836},
-        {"id":145,"name":"Fleetwood—Port Kells","population":124987,"area":69.52,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":146,"name":"Hamilton Mountain","population":107629,"area":33.14,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":147,"name":"Prince Albert","population":80845,"area":18765.59,"colour":"6495ED","count":0,"score":0.0},
-        {"id":148,"name":"Ajax","population":126666,"area":66.64,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":149,"name":"Mount Royal","population":108494,"area":22.63,"colour":"EA6D6A","count":11,"score":33.90144},
-        {"id":150,"name":"Algoma—Manitoulin—Kapuskasing","population":80310,"area":92666.51,"colour":"F4A460","count":0,"score":0.0},
-        {"id":151,"name":"York South—Weston","population":116757,"area":24.8,"colour":"EA6D6A","count":15,"score":40.501133},
-        {"id":152,"name":"Long Range Mountains","population":81716,"area":37319.17,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":153,"name":"Alfred-Pellan","population":102020,"area":113.41,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":154,"name":"Nunavut","population":36858,"area":1836993.78,"colour":"F4A460","count":7,"score":6.1711526},
-        {"id":155,"name":"Toronto Centre","population":119901,"area":5.84,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":156,"name":"Markham—Stouffville","population":135944,"area":282.14,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":158,"name":"Edmonton Riverbend","population":124144,"area":61.45,"colour":"6495ED","count":0,"score":0.0},
-        {"id":159,"name":"Durham","population":150235,"area":868.87,"colour":"6495ED","count":0,"score":0.0},
-        {"id":160,"name":"Courtenay—Alberni","population":125116,"area":7899.15,"colour":"F4A460","count":3,"score":1.7842169},
-        {"id":161,"name":"Ahuntsic-Cartierville","population":118170,"area":21.68,"colour":"EA6D6A","count":41,"score":153.42589},
-        {"id":162,"name":"Labrador","population":26655,"area":258539.4,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":163,"name":"Richmond Hill","population":114180,"area":39.83,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":164,"name":"New Westminster—Burnaby","population":125253,"area":26.29,"colour":"F4A460","count":3,"score":0.9631128},
-        {"id":165,"name":"London West","population":139305,"area":77.32,"colour":"EA6D6A","count":2,"score":5.9591203},
-        {"id":166,"name":"Cape Breton—Canso","population":71380,"area":8733.07,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":167,"name":"Calgary Rocky Ridge","population":145326,"area":91.34,"colour":"6495ED","count":0,"score":0.0},
-        {"id":168,"name":"Mississauga—Erin Mills","population":123371,"area":33.85,"colour":"EA6D6A","count":3,"score":6.5142336},
-        {"id":169,"name":"Oxford","population":124790,"area":2246.42,"colour":"6495ED","count":0,"score":0.0},
-        {"id":170,"name":"Brampton West","population":162353,"area":57.95,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":171,"name":"Souris—Moose Mountain","population":70579,"area":41456.55,"colour":"6495ED","count":0,"score":0.0},
-        {"id":172,"name":"Calgary Shepard","population":163447,"area":178.25,"colour":"6495ED","count":0,"score":0.0},
-        {"id":173,"name":"Vimy","population":119926,"area":34.08,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":174,"name":"Regina—Wascana","population":89087,"area":61.9,"colour":"6495ED","count":0,"score":0.0},
-        {"id":175,"name":"Hastings—Lennox and Addington","population":100636,"area":8233.99,"colour":"6495ED","count":0,"score":0.0},
-        {"id":176,"name":"Battle River—Crowfoot","population":107979,"area":51079.84,"colour":"6495ED","count":1,"score":0.48401305},
-        {"id":177,"name":"Calgary Midnapore","population":129110,"area":82.77,"colour":"6495ED","count":0,"score":0.0},
-        {"id":178,"name":"Windsor—Tecumseh","population":122798,"area":163.02,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":179,"name":"Vancouver East","population":118675,"area":21.66,"colour":"F4A460","count":23,"score":16.865261},
-        {"id":180,"name":"Edmonton—Wetaskiwin","population":209431,"area":4832.59,"colour":"6495ED","count":0,"score":0.0},
-        {"id":181,"name":"Orléans","population":139309,"area":205.82,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":182,"name":"Saint-Laurent","population":102104,"area":42.83,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":183,"name":"LaSalle—Émard—Verdun","population":107564,"area":18.89,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":184,"name":"Winnipeg North","population":101221,"area":37.4,"colour":"EA6D6A","count":7,"score":0.63734746},
-        {"id":185,"name":"Thornhill","population":115292,"area":62.66,"colour":"6495ED","count":8,"score":5.2341633},
-        {"id":186,"name":"Sudbury","population":95537,"area":843.58,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":187,"name":"Shefford","population":115924,"area":1369.28,"colour":"87CEFA","count":1,"score":0.521809},
-        {"id":188,"name":"Saint-Léonard—Saint-Michel","population":115553,"area":20.21,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":189,"name":"Argenteuil—La Petite-Nation","population":102311,"area":4890.26,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":190,"name":"Northumberland—Peterborough South","population":118756,"area":2728.47,"colour":"6495ED","count":0,"score":0.0},
-        {"id":191,"name":"Beauséjour","population":88797,"area":3991.6,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":192,"name":"Gaspésie—Les Îles-de-la-Madeleine","population":75927,"area":16783.86,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":193,"name":"Beauce","population":111034,"area":4100.97,"colour":"6495ED","count":0,"score":0.0},
-        {"id":194,"name":"Abitibi—Témiscamingue","population":103735,"area":33230.9,"colour":"87CEFA","count":1,"score":0.62130326},
-        {"id":195,"name":"Portage—Lisgar","population":100417,"area":12559.18,"colour":"6495ED","count":0,"score":0.0},
-        {"id":196,"name":"Essex","population":134656,"area":1096.69,"colour":"6495ED","count":0,"score":0.0},
-        {"id":197,"name":"Haldimand—Norfolk","population":116706,"area":2848.14,"colour":"6495ED","count":0,"score":0.0},
-        {"id":198,"name":"Calgary Signal Hill","population":122818,"area":65.83,"colour":"6495ED","count":0,"score":0.0},
-        {"id":199,"name":"Louis-Hébert","population":111322,"area":94.4,"colour":"EA6D6A","count":3,"score":30.248034},
-        {"id":200,"name":"Sturgeon River—Parkland","population":124849,"area":3976.8,"colour":"6495ED","count":0,"score":0.0},
-        {"id":201,"name":"Huron—Bruce","population":112929,"area":5598.5,"colour":"6495ED","count":0,"score":0.0},
-        {"id":202,"name":"Saint John—Rothesay","population":81996,"area":390.37,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":203,"name":"Guelph","population":143740,"area":87.43,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":204,"name":"Kitchener—Conestoga","population":107134,"area":896.18,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":205,"name":"Cardigan","population":39866,"area":2547.64,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":206,"name":"Malpeque","population":39731,"area":1606.4,"colour":"EA6D6A","count":1,"score":10.743446},
-        {"id":207,"name":"Cowichan—Malahat—Langford","population":124247,"area":4553.56,"colour":"F4A460","count":0,"score":0.0},
-        {"id":208,"name":"Oxford","population":124790,"area":2246.42,"colour":"6495ED","count":0,"score":0.0},
-        {"id":209,"name":"Gatineau","population":107286,"area":118.79,"colour":"EA6D6A","count":1,"score":1.8418242},
-        {"id":210,"name":"Brandon—Souris","population":89812,"area":17968.6,"colour":"6495ED","count":0,"score":0.0},
-        {"id":211,"name":"Calgary Heritage","population":109141,"area":66.76,"colour":"6495ED","count":5,"score":26.078339},
-        {"id":212,"name":"Etobicoke—Lakeshore","population":141751,"area":39.65,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":213,"name":"Chicoutimi—Le Fjord","population":80593,"area":2534.54,"colour":"6495ED","count":0,"score":0.0},
-        {"id":214,"name":"Hochelaga","population":108264,"area":19.57,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":215,"name":"Windsor West","population":130162,"area":77.6,"colour":"F4A460","count":8,"score":6.147226},
-        {"id":216,"name":"London—Fanshawe","population":127068,"area":114.85,"colour":"F4A460","count":32,"score":36.486362},
-        {"id":217,"name":"Cambridge","population":121301,"area":347.93,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":218,"name":"Saanich—Gulf Islands","population":113541,"area":498.88,"colour":"98CA66","count":10,"score":3.3430169},
-        {"id":219,"name":"Dauphin—Swan River—Neepawa","population":89503,"area":50867.3,"colour":"6495ED","count":0,"score":0.0},
-        {"id":220,"name":"Edmonton West","population":134194,"area":104.54,"colour":"6495ED","count":0,"score":0.0},
-        {"id":221,"name":"Avalon","population":87191,"area":6357.25,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":222,"name":"Ottawa South","population":125090,"area":73.01,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":223,"name":"Scarborough-Guildwood","population":104423,"area":21.35,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":224,"name":"Coquitlam—Port Coquitlam","population":132004,"area":597.3,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":225,"name":"Calgary Centre","population":130010,"area":49.24,"colour":"6495ED","count":0,"score":0.0},
-        {"id":226,"name":"Northwest Territories","population":41070,"area":1127711.92,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":227,"name":"Edmonton Strathcona","population":102188,"area":81.66,"colour":"F4A460","count":128,"score":67.433014},
-        {"id":228,"name":"Kenora","population":64261,"area":285752.27,"colour":"6495ED","count":0,"score":0.0},
-        {"id":229,"name":"Brossard—Saint-Lambert","population":114286,"area":52.75,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":230,"name":"Eglinton—Lawrence","population":115832,"area":22.65,"colour":"EA6D6A","count":4,"score":4.6791835},
-        {"id":231,"name":"Richmond Centre","population":107707,"area":43.0,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":232,"name":"Avignon—La Mitis—Matane—Matapédia","population":70253,"area":14433.2,"colour":"87CEFA","count":1,"score":0.6335409},
-        {"id":233,"name":"Ville-Marie—Le Sud-Ouest—Île-des-Sœurs","population":134555,"area":22.19,"colour":"EA6D6A","count":6,"score":15.204115},
-        {"id":234,"name":"Fundy Royal","population":83721,"area":7225.65,"colour":"6495ED","count":0,"score":0.0},
-        {"id":235,"name":"Charleswood—St. James—Assiniboia—Headingley","population":84767,"area":204.85,"colour":"6495ED","count":9,"score":12.621305},
-        {"id":236,"name":"Kitchener Centre","population":113452,"area":41.46,"colour":"98CA66","count":58,"score":44.60269},
-        {"id":237,"name":"Kootenay—Columbia","population":120759,"area":63345.79,"colour":"6495ED","count":0,"score":0.0},
-        {"id":238,"name":"Egmont","population":35925,"area":1482.86,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":239,"name":"Medicine Hat—Cardston—Warner","population":108391,"area":29137.38,"colour":"6495ED","count":0,"score":0.0},
-        {"id":240,"name":"Vancouver Quadra","population":109328,"area":40.29,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":241,"name":"Flamborough—Glanbrook","population":125692,"area":886.65,"colour":"6495ED","count":0,"score":0.0},
-        {"id":242,"name":"Ottawa Centre","population":126360,"area":32.43,"colour":"EA6D6A","count":3,"score":5.4014153},
-        {"id":243,"name":"Perth Wellington","population":113910,"area":3580.35,"colour":"6495ED","count":0,"score":0.0},
-        {"id":244,"name":"Markham—Thornhill","population":97510,"area":41.74,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":245,"name":"Vancouver Granville","population":109799,"area":22.4,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":246,"name":"Saint-Jean","population":114617,"area":686.27,"colour":"87CEFA","count":10,"score":8.083225},
-        {"id":247,"name":"Pickering—Uxbridge","population":120742,"area":651.62,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":248,"name":"St. John's South—Mount Pearl","population":81544,"area":422.71,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":249,"name":"Durham","population":150235,"area":868.87,"colour":"6495ED","count":0,"score":0.0},
-        {"id":250,"name":"Don Valley West","population":101959,"area":30.11,"colour":"EA6D6A","count":14,"score":28.991508},
-        {"id":251,"name":"Cypress Hills—Grasslands","population":68314,"area":75611.48,"colour":"6495ED","count":1,"score":0.80976903},
-        {"id":252,"name":"Charlesbourg—Haute-Saint-Charles","population":109690,"area":111.29,"colour":"6495ED","count":4,"score":3.9870422},
-        {"id":253,"name":"Repentigny","population":119204,"area":198.59,"colour":"87CEFA","count":0,"score":0.0},
-        {"id":254,"name":"South Shore—St. Margarets","population":94482,"area":8461.63,"colour":"6495ED","count":0,"score":0.0},
-        {"id":255,"name":"Berthier—Maskinongé","population":105280,"area":4114.68,"colour":"87CEFA","count":0,"score":0.0},
-        {"id":256,"name":"Moncton—Riverview—Dieppe","population":101237,"area":154.68,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":257,"name":"Bécancour—Nicolet—Saurel","population":96439,"area":2743.24,"colour":"87CEFA","count":0,"score":0.0},
-        {"id":258,"name":"Carleton","population":131375,"area":1177.87,"colour":"6495ED","count":9,"score":2.2852268},
-        {"id":259,"name":"Thunder Bay—Rainy River","population":82357,"area":32536.42,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":260,"name":"Delta","population":110721,"area":186.77,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":261,"name":"Richmond—Arthabaska","population":110651,"area":3427.45,"colour":"DCDCDC","count":0,"score":0.0},
-        {"id":262,"name":"Saskatoon West","population":87855,"area":90.73,"colour":"6495ED","count":1,"score":1.2697606},
-        {"id":263,"name":"Lanark—Frontenac—Kingston","population":111424,"area":6353.87,"colour":"6495ED","count":1,"score":1.7288774},
-        {"id":264,"name":"Calgary Nose Hill","population":115287,"area":57.97,"colour":"6495ED","count":1,"score":0.72623223},
-        {"id":265,"name":"Banff—Airdrie","population":155580,"area":12304.43,"colour":"6495ED","count":0,"score":0.0},
-        {"id":266,"name":"King—Vaughan","population":147695,"area":424.52,"colour":"6495ED","count":0,"score":0.0},
-        {"id":267,"name":"Marc-Aurèle-Fortin","population":104636,"area":52.55,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":268,"name":"Honoré-Mercier","population":104578,"area":37.71,"colour":"DCDCDC","count":0,"score":0.0},
-        {"id":269,"name":"Bonavista—Burin—Trinity","population":71898,"area":16529.46,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":270,"name":"Longueuil—Charles-LeMoyne","population":112257,"area":37.44,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":271,"name":"Lambton—Kent—Middlesex","population":111242,"area":4919.1,"colour":"6495ED","count":0,"score":0.0},
-        {"id":272,"name":"Nipissing—Timiskaming","population":89781,"area":13115.67,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":273,"name":"Bruce—Grey—Owen Sound","population":113348,"area":6089.27,"colour":"6495ED","count":0,"score":0.0},
-        {"id":274,"name":"Brampton North","population":125141,"area":33.21,"colour":"EA6D6A","count":2,"score":6.84697},
-        {"id":275,"name":"Vancouver South","population":109339,"area":20.69,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":276,"name":"York Centre","population":108307,"area":35.12,"colour":"EA6D6A","count":7,"score":11.155201},
-        {"id":277,"name":"Sackville—Preston—Chezzetcook","population":89524,"area":645.18,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":278,"name":"Surrey Centre","population":131670,"area":40.34,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":279,"name":"LaSalle—Émard—Verdun","population":107564,"area":18.89,"colour":"87CEFA","count":0,"score":0.0},
-        {"id":280,"name":"Saint-Hyacinthe—Bagot","population":105086,"area":1881.6,"colour":"87CEFA","count":0,"score":0.0},
-        {"id":281,"name":"Lac-Saint-Louis","population":110093,"area":78.21,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":282,"name":"Regina—Qu'Appelle","population":78140,"area":12723.61,"colour":"6495ED","count":0,"score":0.0},
-        {"id":283,"name":"Vaudreuil—Soulanges","population":129612,"area":392.62,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":284,"name":"Haliburton—Kawartha Lakes—Brock","population":122401,"area":7772.15,"colour":"6495ED","count":0,"score":0.0},
-        {"id":285,"name":"Dufferin—Caledon","population":142838,"area":2175.59,"colour":"6495ED","count":3,"score":3.2175722},
-        {"id":286,"name":"Nickel Belt","population":94947,"area":26564.18,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":287,"name":"Humber River—Black Creek","population":111593,"area":30.61,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":288,"name":"Châteauguay—Lacolle","population":105111,"area":903.49,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":289,"name":"Sault Ste. Marie","population":79331,"area":5540.4,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":290,"name":"Bow River","population":119458,"area":23780.27,"colour":"6495ED","count":0,"score":0.0},
-        {"id":291,"name":"Barrie—Springwater—Oro-Medonte","population":106871,"area":964.36,"colour":"6495ED","count":0,"score":0.0},
-        {"id":292,"name":"Brampton East","population":131677,"area":84.48,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":293,"name":"Brampton South","population":132752,"area":46.63,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":294,"name":"Jonquière","population":91073,"area":37641.59,"colour":"87CEFA","count":0,"score":0.0},
-        {"id":295,"name":"Terrebonne","population":119944,"area":153.76,"colour":"87CEFA","count":0,"score":0.0},
-        {"id":296,"name":"Burnaby South","population":120305,"area":46.06,"colour":"F4A460","count":36,"score":33.763187},
-        {"id":297,"name":"Coast of Bays—Central—Notre Dame","population":74201,"area":38674.01,"colour":"6495ED","count":0,"score":0.0},
-        {"id":298,"name":"Vaughan—Woodbridge","population":106810,"area":79.59,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":299,"name":"Yellowhead","population":99218,"area":77133.53,"colour":"6495ED","count":0,"score":0.0},
-        {"id":300,"name":"Mississauga—Lakeshore","population":117095,"area":60.12,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":301,"name":"Mississauga—Lakeshore","population":117095,"area":60.12,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":302,"name":"Brome—Missisquoi","population":113913,"area":2779.12,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":303,"name":"Joliette","population":112418,"area":8124.04,"colour":"87CEFA","count":0,"score":0.0},
-        {"id":304,"name":"Regina—Lewvan","population":98492,"area":58.01,"colour":"6495ED","count":0,"score":0.0},
-        {"id":305,"name":"Toronto—St. Paul's","population":116953,"area":13.12,"colour":"6495ED","count":2,"score":17.909914},
-        {"id":306,"name":"Miramichi—Grand Lake","population":57520,"area":16837.24,"colour":"6495ED","count":0,"score":0.0},
-        {"id":307,"name":"Chilliwack—Hope","population":112037,"area":3283.75,"colour":"6495ED","count":0,"score":0.0},
-        {"id":308,"name":"Lakeland","population":105993,"area":31028.15,"colour":"6495ED","count":0,"score":0.0},
-        {"id":309,"name":"Kanata—Carleton","population":116651,"area":760.74,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":310,"name":"Hamilton West—Ancaster—Dundas","population":117565,"area":101.02,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":311,"name":"Aurora—Oak Ridges—Richmond Hill","population":118883,"area":94.47,"colour":"EA6D6A","count":3,"score":5.6418552},
-        {"id":312,"name":"Montcalm","population":118746,"area":873.04,"colour":"87CEFA","count":0,"score":0.0},
-        {"id":313,"name":"La Prairie","population":114968,"area":277.52,"colour":"87CEFA","count":1,"score":0.8508249},
-        {"id":314,"name":"Lethbridge","population":123847,"area":2958.96,"colour":"6495ED","count":0,"score":0.0},
-        {"id":315,"name":"St. John's East","population":87345,"area":328.38,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":316,"name":"Saskatoon—University","population":88348,"area":68.7,"colour":"6495ED","count":0,"score":0.0},
-        {"id":317,"name":"Moose Jaw—Lake Centre—Lanigan","population":80547,"area":32079.29,"colour":"6495ED","count":0,"score":0.0},
-        {"id":318,"name":"Papineau","population":110813,"area":9.9,"colour":"EA6D6A","count":30,"score":9.448879},
-        {"id":319,"name":"Longueuil—Saint-Hubert","population":115082,"area":55.23,"colour":"87CEFA","count":0,"score":0.0},
-        {"id":320,"name":"Whitby","population":138501,"area":146.69,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":321,"name":"Edmonton Mill Woods","population":125992,"area":51.22,"colour":"6495ED","count":0,"score":0.0},
-        {"id":322,"name":"Mississauga—Streetsville","population":116864,"area":46.53,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":323,"name":"Newmarket—Aurora","population":127134,"area":59.47,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":324,"name":"Milton","population":136993,"area":447.36,"colour":"EA6D6A","count":13,"score":5.936751},
-        {"id":325,"name":"Langley—Aldergrove","population":133168,"area":373.15,"colour":"6495ED","count":1,"score":1.0778883},
-        {"id":326,"name":"Saint Boniface—Saint Vital","population":95514,"area":62.9,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":327,"name":"Ottawa West—Nepean","population":116409,"area":66.0,"colour":"EA6D6A","count":22,"score":86.271126},
-        {"id":328,"name":"Elgin—Middlesex—London","population":126428,"area":2482.02,"colour":"6495ED","count":0,"score":0.0},
-        {"id":329,"name":"Desnethé—Missinippi—Churchill River","population":71488,"area":296772.09,"colour":"6495ED","count":0,"score":0.0},
-        {"id":330,"name":"Bellechasse—Les Etchemins—Lévis","population":120179,"area":3201.11,"colour":"6495ED","count":0,"score":0.0},
-        {"id":331,"name":"Peace River—Westlock","population":107223,"area":106395.3,"colour":"6495ED","count":0,"score":0.0},
-        {"id":332,"name":"Beauport—Limoilou","population":96064,"area":33.35,"colour":"87CEFA","count":0,"score":0.0},
-        {"id":333,"name":"Trois-Rivières","population":114064,"area":125.31,"colour":"87CEFA","count":0,"score":0.0},
-        {"id":334,"name":"Parkdale—High Park","population":106750,"area":14.83,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":335,"name":"Mission—Matsqui—Fraser Canyon","population":101216,"area":21330.06,"colour":"6495ED","count":0,"score":0.0},
-        {"id":336,"name":"Spadina—Fort York","population":136213,"area":12.76,"colour":"DCDCDC","count":6,"score":9.688201},
-        {"id":337,"name":"Yorkton—Melville","population":71220,"area":41801.6,"colour":"6495ED","count":0,"score":0.0},
-        {"id":338,"name":"Grande Prairie—Mackenzie","population":119586,"area":111864.57,"colour":"6495ED","count":0,"score":0.0},
-        {"id":339,"name":"Saskatoon—Grasswood","population":93277,"area":335.23,"colour":"6495ED","count":0,"score":0.0},
-        {"id":340,"name":"Calgary Confederation","population":124064,"area":54.24,"colour":"6495ED","count":0,"score":0.0},
-        {"id":341,"name":"West Vancouver—Sunshine Coast—Sea to Sky Country","population":131206,"area":12990.95,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":342,"name":"North Vancouver","population":123025,"area":335.08,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":343,"name":"Bay of Quinte","population":116016,"area":1624.98,"colour":"6495ED","count":0,"score":0.0},
-        {"id":344,"name":"New Brunswick Southwest","population":67781,"area":9885.7,"colour":"6495ED","count":0,"score":0.0},
-        {"id":345,"name":"Scarborough—Agincourt","population":103449,"area":25.94,"colour":"EA6D6A","count":0,"score":0.0},
-        {"id":346,"name":"Scarborough Centre","population":113104,"area":28.12,"colour":"EA6D6A","count":34,"score":110.23571},
-        {"id":347,"name":"Port Moody—Coquitlam","population":114853,"area":95.16,"colour":"F4A460","count":0,"score":0.0},
-        {"id":348,"name":"Prince George—Peace River—Northern Rockies","population":108998,"area":249105.22,"colour":"6495ED","count":0,"score":0.0},
-        {"id":349,"name":"Pierrefonds—Dollard","population":109497,"area":50.74,"colour":"EA6D6A","count":5,"score":20.920504}
-    ]"#
-}
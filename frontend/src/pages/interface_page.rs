@@ -1,14 +1,88 @@
 use crate::components::charts::Charts;
+use crate::components::navbar::Route;
 use crate::components::speech_overlay::SpeechOverlay;
 use crate::pages::error_page::error_page;
 use crate::util::*;
 use crate::State;
-use aho_corasick::AhoCorasick;
-use common::models::{BreakdownType, CancelRequest};
+use common::models::BreakdownType;
+use common::search::normalise;
 use gloo::utils::body;
-use regex::Regex;
-use wasm_bindgen_futures::spawn_local;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 use yew::prelude::*;
+use yew_router::prelude::*;
+
+/// The interface state as it is encoded in the query string of a search url, so
+/// that searches can be bookmarked and shared.
+///
+/// For example, `/search?q=carbon+tax&charts=party,gender&counts=true` restores
+/// a search for "carbon tax" with the party and gender charts showing total counts.
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct SearchQuery {
+    /// The search term.
+    pub q: String,
+
+    /// The visible charts, as a comma-separated list of breakdown types and `pop`.
+    pub charts: String,
+
+    /// Whether the charts are showing total counts.
+    pub counts: bool,
+
+    /// Whether the breakdown charts are showing per-speaker dispersion.
+    pub spread: bool,
+
+    /// The open speech overlay selection as a breakdown type and id, like
+    /// `party-3`, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub overlay: Option<String>,
+
+    /// The heading of the open speech overlay, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub heading: Option<String>,
+}
+
+impl Default for SearchQuery {
+    fn default() -> Self {
+        SearchQuery {
+            q: String::new(),
+            charts: "party".to_string(),
+            counts: false,
+            spread: false,
+            overlay: None,
+            heading: None,
+        }
+    }
+}
+
+impl SearchQuery {
+    /// Returns the query for a search for the term with the default charts.
+
+    pub fn search(term: &str) -> SearchQuery {
+        SearchQuery {
+            q: term.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    /// Whether the named chart is visible.
+
+    fn showing(&self, chart: &str) -> bool {
+        self.charts.split(',').any(|c| c == chart)
+    }
+
+    /// Returns the speech overlay selection, if there is a valid one.
+
+    fn selection(&self) -> Option<OverlaySelection> {
+        let (breakdown_type, id) = self.overlay.as_ref()?.split_once('-')?;
+        Some(OverlaySelection {
+            breakdown_type: BreakdownType::from_str(breakdown_type).ok()?,
+            id: id.parse().ok()?,
+            heading: self.heading.clone().unwrap_or_default(),
+        })
+    }
+}
 
 /// The main interface page for this tool.
 ///
@@ -18,26 +92,80 @@ use yew::prelude::*;
 #[function_component(InterfacePage)]
 pub fn interface_page() -> Html {
     let app_state = use_context::<State>();
+    let navigator = use_navigator();
+    let location = use_location();
     let failed = use_state(|| false);
 
+    // the state we were loaded with, restored from the url
+    let initial = use_memo((), |_| {
+        location
+            .and_then(|l| l.query::<SearchQuery>().ok())
+            .unwrap_or_default()
+    });
+    let initial_selection = initial.selection();
+
     let show_charts = use_state(|| false);
-    let show_party = use_state(|| true);
-    let show_gender = use_state(|| false);
-    let show_province = use_state(|| false);
-    let show_class = use_state(|| false);
-    let show_speaker = use_state(|| false);
-    let show_pop = use_state(|| false);
-    let input_value = use_state(|| String::from(""));
-    let word = use_state(|| String::from(""));
-    let show_counts = use_state(|| false);
-    let speech_overlay_word = use_state(|| String::from(""));
-    let speech_overlay_visible = use_state(|| false);
-    let selection = use_state(|| OverlaySelection {
-        breakdown_type: BreakdownType::Party,
-        id: 0,
-        heading: String::from(""),
+    let show_party = use_state(|| initial.showing("party"));
+    let show_gender = use_state(|| initial.showing("gender"));
+    let show_province = use_state(|| initial.showing("province"));
+    let show_class = use_state(|| initial.showing("class"));
+    let show_speaker = use_state(|| initial.showing("speaker"));
+    let show_pop = use_state(|| initial.showing("pop"));
+    let input_value = use_state(|| initial.q.clone());
+    let word = use_state(|| normalise(&initial.q));
+    let show_counts = use_state(|| initial.counts);
+    let show_dispersion = use_state(|| initial.spread);
+    let speech_overlay_word = use_state(|| normalise(&initial.q));
+    let speech_overlay_visible = use_state(|| initial_selection.is_some() && !initial.q.is_empty());
+    let selection = use_state(|| {
+        initial_selection.unwrap_or(OverlaySelection {
+            breakdown_type: BreakdownType::Party,
+            id: 0,
+            heading: String::from(""),
+        })
     });
 
+    // cover the body if we were loaded with the speech overlay open
+    {
+        let speech_overlay_visible = speech_overlay_visible.clone();
+        use_effect_with((), move |_| {
+            if *speech_overlay_visible {
+                body().set_class_name("body-covered");
+            }
+        });
+    }
+
+    // keep the url in sync with the interface state
+    {
+        let charts = [
+            ("party", *show_party),
+            ("gender", *show_gender),
+            ("province", *show_province),
+            ("class", *show_class),
+            ("speaker", *show_speaker),
+            ("pop", *show_pop),
+        ]
+        .iter()
+        .filter(|(_, showing)| *showing)
+        .map(|(chart, _)| *chart)
+        .collect::<Vec<&str>>()
+        .join(",");
+        let overlay_open = *speech_overlay_visible && selection.id != 0;
+        let query = SearchQuery {
+            q: (*word).clone(),
+            charts,
+            counts: *show_counts,
+            spread: *show_dispersion,
+            overlay: overlay_open.then(|| format!("{}-{}", selection.breakdown_type, selection.id)),
+            heading: overlay_open.then(|| selection.heading.clone()),
+        };
+        use_effect_with(query, move |query| {
+            if let Some(navigator) = navigator {
+                let _ = navigator.replace_with_query(&Route::Interface, query);
+            }
+        });
+    }
+
     fn build_on(state: UseStateHandle<bool>) -> Callback<MouseEvent> {
         Callback::from(move |e: MouseEvent| {
             if let Some(input) = e.target_dyn_into::<web_sys::HtmlInputElement>() {
@@ -53,6 +181,7 @@ pub fn interface_page() -> Html {
     let on_speaker = build_on(show_speaker.clone());
     let on_pop = build_on(show_pop.clone());
     let on_show_counts = build_on(show_counts.clone());
+    let on_show_dispersion = build_on(show_dispersion.clone());
 
     let on_input = {
         let input_value = input_value.clone();
@@ -70,15 +199,9 @@ pub fn interface_page() -> Html {
         let failed = failed.clone();
         Callback::from(move |e: SubmitEvent| {
             e.prevent_default();
-            let input_value = input_value.clone();
-            let word = word.clone();
-            let app_state = app_state.clone();
-            if let Some(state) = app_state {
-                spawn_local(async move {
-                    let cancel_request = CancelRequest { uuid: state.uuid };
-                    let _ = put("api/cancel", cancel_request).await;
-                    word.set(clean(&(*input_value)));
-                });
+            // each chart cancels its own stale request when the word changes
+            if app_state.is_some() {
+                word.set(normalise(&input_value));
             } else {
                 failed.set(true);
             }
@@ -137,26 +260,26 @@ pub fn interface_page() -> Html {
                             </div>
                             <div>
                                 <label for="show_gender"> {"gender"}</label>
-                                <input type="checkbox" id="show_gender" onclick={on_gender} />
+                                <input type="checkbox" id="show_gender" onclick={on_gender} checked={*show_gender}/>
                             </div>
                             if !provincial {
                                 <div>
                                     <label for="show_province"> {"province"}</label>
-                                    <input type="checkbox" id="show_province" onclick={on_province} />
+                                    <input type="checkbox" id="show_province" onclick={on_province} checked={*show_province}/>
                                 </div>
                                 <div>
                                     <label for="show_class"> {"class"}</label>
-                                    <input type="checkbox" id="show_class" onclick={on_class} />
+                                    <input type="checkbox" id="show_class" onclick={on_class} checked={*show_class}/>
                                 </div>
                             }
                             <div>
                                 <label for="show_speaker"> {"speaker"}</label>
-                                <input type="checkbox" id="show_speaker" onclick={on_speaker} />
+                                <input type="checkbox" id="show_speaker" onclick={on_speaker} checked={*show_speaker}/>
                             </div>
                             if !provincial {
                                 <div>
                                     <label for="show_pop"> {"pop density"}</label>
-                                    <input type="checkbox" id="show_pop" onclick={on_pop} />
+                                    <input type="checkbox" id="show_pop" onclick={on_pop} checked={*show_pop}/>
                                 </div>
                             }
                         </div>
@@ -167,7 +290,11 @@ pub fn interface_page() -> Html {
                     </div>
                     <div class="form-section">
                         <label for="show_counts"> {"total counts"}</label>
-                        <input type="checkbox" id="show_counts" onclick={on_show_counts}/>
+                        <input type="checkbox" id="show_counts" onclick={on_show_counts} checked={*show_counts}/>
+                    </div>
+                    <div class="form-section">
+                        <label for="show_dispersion"> {"speaker spread"}</label>
+                        <input type="checkbox" id="show_dispersion" onclick={on_show_dispersion} checked={*show_dispersion}/>
                     </div>
                     <div class="form-section">
                         <input type="submit" value="submit" class="button"/>
@@ -179,6 +306,7 @@ pub fn interface_page() -> Html {
                 <Charts
                     word={(*word).clone()}
                     show_counts={*show_counts}
+                    show_dispersion={*show_dispersion}
                     show_party={*show_party}
                     show_gender={*show_gender}
                     show_province={*show_province}
@@ -202,22 +330,3 @@ pub fn interface_page() -> Html {
         </div>
     }
 }
-
-fn clean(text: &str) -> String {
-    let patterns = &[
-        ".", ",", ";", ":", "!", "?", "\'", "\"", "”", "“", "’", "‘", "(", ")", "[", "]", "{", "}",
-        "«", "»",
-    ];
-    let ac = AhoCorasick::builder().build(patterns).unwrap();
-    let mut clean = String::new();
-    ac.replace_all_with(text, &mut clean, |_, _, dst| {
-        dst.push_str("");
-        true
-    });
-
-    Regex::new(r#"\s+"#)
-        .unwrap()
-        .replace_all(&clean, " ")
-        .replace("—", " ")
-        .to_lowercase()
-}
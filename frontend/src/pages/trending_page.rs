@@ -0,0 +1,99 @@
+use crate::components::popular_searches::{fetch_trending, search_link};
+use common::models::{TrendingPeriod, TrendingResponse};
+use time::macros::format_description;
+use time::OffsetDateTime;
+use wasm_bindgen_futures::spawn_local;
+use yew::prelude::*;
+
+/// A page showing the most searched terms and the slowest chart queries over the
+/// last day or week.
+
+#[function_component(TrendingPage)]
+pub fn trending_page() -> Html {
+    let period = use_state(TrendingPeriod::default);
+    let data = use_state(|| None::<Option<TrendingResponse>>);
+
+    {
+        let data = data.clone();
+        use_effect_with(*period, move |period| {
+            let period = *period;
+            data.set(None);
+            spawn_local(async move {
+                data.set(Some(fetch_trending(period).await));
+            });
+        });
+    }
+
+    let set_period = |p: TrendingPeriod| {
+        let period = period.clone();
+        Callback::from(move |_| period.set(p))
+    };
+    let period_class = |p: TrendingPeriod| {
+        if *period == p {
+            "button highlight"
+        } else {
+            "button"
+        }
+    };
+
+    let time_format =
+        format_description!("[month repr:short] [day padding:none], [hour]:[minute] UTC");
+    let finished = |timestamp: i64| {
+        OffsetDateTime::from_unix_timestamp(timestamp)
+            .ok()
+            .and_then(|t| t.format(time_format).ok())
+            .unwrap_or_default()
+    };
+
+    let content = match &*data {
+        None => html! {
+            <div class="loader"></div>
+        },
+        Some(None) => html! {
+            <p>{"The search analytics couldn't be loaded. Try again later."}</p>
+        },
+        Some(Some(trending)) if trending.terms.is_empty() => html! {
+            <p>{format!("Nobody has searched anything in the last {}.", trending.period)}</p>
+        },
+        Some(Some(trending)) => html! {
+            <>
+                <h2>{"Most searched"}</h2>
+                <p>{"Each person is counted once per search term, however many charts they looked at."}</p>
+                <table class="trending-table">
+                    <tr><th>{"term"}</th><th>{"searches"}</th></tr>
+                    { for trending.terms.iter().map(|t| html! {
+                        <tr>
+                            <td>{ search_link(&t.term) }</td>
+                            <td class="number">{t.searches}</td>
+                        </tr>
+                    }) }
+                </table>
+
+                <h2>{"Slowest queries"}</h2>
+                <table class="trending-table">
+                    <tr><th>{"term"}</th><th>{"chart"}</th><th>{"seconds"}</th><th>{"finished"}</th></tr>
+                    { for trending.slowest.iter().map(|q| html! {
+                        <tr class={if q.outcome == "ok" {""} else {"failed"}}>
+                            <td>{ search_link(&q.term) }</td>
+                            <td>{&q.chart}</td>
+                            <td class="number">{format!("{:.1}", q.duration_ms as f64 / 1000.0)}</td>
+                            <td>{finished(q.timestamp)}</td>
+                        </tr>
+                    }) }
+                </table>
+            </>
+        },
+    };
+
+    html! {
+        <div class="info">
+            <h2>{"Trending searches"}</h2>
+            <p>{"What people have been searching for recently. Click a term to search it yourself."}</p>
+            <div class="trending-periods">
+                <button class={period_class(TrendingPeriod::Day)} onclick={set_period(TrendingPeriod::Day)}>{"last day"}</button>
+                <button class={period_class(TrendingPeriod::Week)} onclick={set_period(TrendingPeriod::Week)}>{"last week"}</button>
+            </div>
+            { content }
+        </div>
+    }
+}
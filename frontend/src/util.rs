@@ -1,6 +1,14 @@
-use common::models::BreakdownType;
+use common::models::{BreakdownType, ErrorResponse, SingleCancelRequest};
+use gloo::file::{Blob, ObjectUrl};
+use gloo::timers::callback::Timeout;
+use gloo::utils::document;
 use gloo_net::http::{Request, Response};
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use js_sys::{Reflect, Uint8Array};
+use wasm_bindgen::JsCast;
+use uuid::Uuid;
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::{HtmlAnchorElement, ReadableStreamDefaultReader};
 
 // todo think about moving structs to a models file
 
@@ -37,3 +45,101 @@ pub async fn put<T>(uri: &str, body: T) -> Result<Response>
     
     Ok(resp)
 }
+
+/// Get the info page demo dataset with the given name from the backend.
+
+pub async fn get_demo<T>(name: &str) -> Result<T>
+    where T: serde::de::DeserializeOwned
+{
+    let resp = Request::get(&format!("/api/demo/{}", name)).send().await?;
+    if resp.status() != 200 {
+        return Err(anyhow!("no demo named {}", name));
+    }
+    
+    Ok(serde_json::from_str::<T>(&resp.text().await?)?)
+}
+
+/// Ask the backend to cancel a single data request made by the given user. This
+/// happens in the background, and failures are ignored since the request will
+/// finish on its own anyway.
+
+pub fn cancel_request(uuid: Uuid, request_id: Uuid) {
+    spawn_local(async move {
+        let cancel_request = SingleCancelRequest { uuid, request_id };
+        let _ = put("/api/cancel/request", cancel_request).await;
+    });
+}
+
+/// Read a streamed response body as it arrives, calling back with each complete
+/// line. Returns once the body ends.
+///
+/// The body is read as raw bytes and split on newlines before decoding, so that
+/// characters split across network chunks are never decoded in halves.
+
+pub async fn read_lines(resp: &Response, mut on_line: impl FnMut(&[u8])) -> Result<()> {
+    let body = resp.body().ok_or_else(|| anyhow!("response has no body"))?;
+    let reader = body
+        .get_reader()
+        .dyn_into::<ReadableStreamDefaultReader>()
+        .map_err(|_| anyhow!("couldn't get body reader"))?;
+    
+    let mut buffer = vec![];
+    loop {
+        let result = JsFuture::from(reader.read())
+            .await
+            .map_err(|_| anyhow!("couldn't read body"))?;
+        let done = Reflect::get(&result, &"done".into())
+            .map_err(|_| anyhow!("couldn't read body"))?;
+        if done.as_bool().unwrap_or(true) {
+            break;
+        }
+        let value = Reflect::get(&result, &"value".into())
+            .map_err(|_| anyhow!("couldn't read body"))?;
+        buffer.extend(Uint8Array::new(&value).to_vec());
+        
+        while let Some(end) = buffer.iter().position(|b| *b == b'\n') {
+            let line = buffer.drain(..=end).collect::<Vec<u8>>();
+            on_line(&line[..end]);
+        }
+    }
+    
+    Ok(())
+}
+
+/// Read the json error envelope from a failed response, if it has one.
+
+pub async fn error_response(resp: Response) -> Option<ErrorResponse> {
+    let text = resp.text().await.ok()?;
+    serde_json::from_str::<ErrorResponse>(&text).ok()
+}
+
+/// Prompt the browser to download the given contents as a file, via an object url.
+
+pub fn download(filename: &str, mime_type: &str, contents: &str) -> Result<()> {
+    let url = ObjectUrl::from(Blob::new_with_options(contents, Some(mime_type)));
+    download_url(filename, &url)?;
+    
+    // give the browser a moment to start the download before revoking the url
+    Timeout::new(1000, move || drop(url)).forget();
+    
+    Ok(())
+}
+
+/// Prompt the browser to download the file at the given url, which may be an object
+/// or data url.
+///
+/// This works by pointing a temporary anchor element at the url and clicking it.
+
+pub fn download_url(filename: &str, url: &str) -> Result<()> {
+    let anchor = document()
+        .create_element("a")
+        .map_err(|_| anyhow!("couldn't create anchor"))?
+        .dyn_into::<HtmlAnchorElement>()
+        .map_err(|_| anyhow!("couldn't cast anchor"))?;
+    
+    anchor.set_href(url);
+    anchor.set_download(filename);
+    anchor.click();
+    
+    Ok(())
+}
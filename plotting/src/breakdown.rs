@@ -0,0 +1,315 @@
+use crate::{colour, label_style, Chart, ChartError, PointMapping};
+use common::models::{BreakdownResponse, BreakdownType, DispersionResponse};
+use plotters::coord::Shift;
+use plotters::prelude::SegmentValue::CenterOf;
+use plotters::prelude::*;
+use std::cmp::{max, min};
+
+/// A breakdown chart bar mapping for interactivity.
+///
+/// The struct holds the top, left, bottom, and right edges of an interactive box
+/// (a bar on the chart, or both bars if we're showing total counts) and the id of
+/// of party/gender/etc. represented by that box.
+
+#[derive(Default, Clone)]
+pub struct BarMapping {
+    pub top: i32,
+    pub left: i32,
+    pub bottom: i32,
+    pub right: i32,
+    pub id: i32,
+}
+
+/// A breakdown chart of some type, borrowing the data it draws.
+
+pub struct BreakdownChart<'a> {
+    /// The type of breakdown.
+    pub breakdown_type: &'a BreakdownType,
+
+    /// The data for this chart.
+    pub data: &'a [BreakdownResponse],
+
+    /// The per-speaker data to overlay on each bar. Empty if we aren't showing the
+    /// dispersion overlay.
+    pub dispersion: &'a [DispersionResponse],
+
+    /// Whether we are showing total counts instead of adjusted scores.
+    pub show_counts: bool,
+}
+
+impl BreakdownChart<'_> {
+    /// Returns a sane calculated width for the chart, given the window width.
+
+    pub fn width(&self, window_width: f64) -> u32 {
+        let segs = self.data.len() as u32;
+        let ww = (window_width * 0.97) as u32;
+        let thick = min(max(segs * 90, ww), segs * 160);
+        let thin = min(max(segs * 80, ww), segs * 160);
+        match self.breakdown_type {
+            BreakdownType::Speaker => thick,
+            BreakdownType::Party => thin,
+            BreakdownType::Gender => thin,
+            BreakdownType::Province => thick,
+            BreakdownType::Class => thin,
+        }
+    }
+
+    /// Draws the chart on a plotters drawing area and returns the coordinate
+    /// mappings for its bars and speaker points.
+    ///
+    /// The reference width (the window width, for the canvas) sizes the labels, and
+    /// the scale is the number of real pixels per CSS pixel.
+
+    pub fn draw<DB: DrawingBackend>(
+        &self,
+        drawing_area: &DrawingArea<DB, Shift>,
+        width: f64,
+        scale: f64,
+        caption: Option<&str>,
+    ) -> Result<(Vec<BarMapping>, Vec<PointMapping>), ChartError> {
+        let mut data = self.data.iter().collect::<Vec<&BreakdownResponse>>();
+        data.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+        let mut label_size = (width.sqrt() / 2.5 * scale) as u32;
+
+        if *self.breakdown_type == BreakdownType::Speaker
+            || *self.breakdown_type == BreakdownType::Province
+        {
+            label_size = label_size.saturating_sub(4);
+        }
+
+        let x_axis = data.iter().map(|r| r.name.clone()).collect::<Vec<String>>();
+        let mut y_max = data
+            .iter()
+            .map(|r| r.score)
+            .max_by(|a, b| a.total_cmp(b))
+            .ok_or(ChartError)?;
+
+        // sorted per-speaker scores for each bar, if we're showing them
+        let members = if self.dispersing() {
+            data.iter()
+                .map(|r| {
+                    let mut m = self
+                        .dispersion
+                        .iter()
+                        .filter(|d| d.breakdown_id == r.id)
+                        .collect::<Vec<&DispersionResponse>>();
+                    m.sort_by(|a, b| a.score.total_cmp(&b.score));
+                    m
+                })
+                .collect::<Vec<Vec<&DispersionResponse>>>()
+        } else {
+            vec![]
+        };
+        y_max = members
+            .iter()
+            .flatten()
+            .map(|d| d.score)
+            .fold(y_max, f64::max);
+        let c_max = data.iter().map(|r| r.count).max().ok_or(ChartError)? as f64;
+
+        let mut chart = ChartBuilder::on(drawing_area)
+            .x_label_area_size((40.0 * scale) as u32)
+            .y_label_area_size((70.0 * scale) as u32)
+            .right_y_label_area_size(if self.show_counts {
+                (60.0 * scale) as u32
+            } else {
+                0
+            })
+            .caption(
+                caption.unwrap_or(" "),
+                ("sans-serif", (30.0 * scale) as u32, &WHITE),
+            )
+            .build_cartesian_2d(x_axis.into_segmented(), 0.0..y_max)?
+            .set_secondary_coord(0.0..data.len() as f32, 0.0..c_max);
+
+        let bold_line = colour("97948f")?;
+        let light_line = colour("67635c")?;
+
+        label_size = max(
+            (label_size as f64 * (1.0 + (scale * 0.1))) as u32,
+            (8.0 * scale) as u32,
+        );
+        let desc_style = label_style((16.0 * scale) as u32);
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .x_desc(format!("{}", self.breakdown_type))
+            .x_label_style(label_style(label_size))
+            .x_label_formatter(&|v| {
+                if let CenterOf(s) = v {
+                    s.to_string()
+                } else {
+                    "".to_string()
+                }
+            })
+            .y_desc("word count per 100,000")
+            .y_label_style(label_style(label_size))
+            .axis_desc_style(desc_style.clone())
+            .bold_line_style(bold_line)
+            .light_line_style(light_line)
+            .draw()?;
+
+        if self.show_counts {
+            chart
+                .configure_secondary_axes()
+                .y_desc("total word count")
+                .label_style(label_style(label_size))
+                .axis_desc_style(desc_style)
+                .y_label_formatter(&|v| format!("{}", *v as u32))
+                .draw()?;
+        }
+
+        let mut bar_mappings = vec![];
+        for (i, r) in data.iter().enumerate() {
+            let left = i as f32 + if self.show_counts { 0.15 } else { 0.20 };
+            let right = i as f32 + if self.show_counts { 0.85 } else { 0.80 };
+            let mut top = r.score * (c_max / y_max);
+            if self.show_counts {
+                top = f64::max(r.count as f64, top)
+            }
+            let tl = chart.borrow_secondary().backend_coord(&(left, top));
+            let br = chart.borrow_secondary().backend_coord(&(right, 0.0));
+            bar_mappings.push(BarMapping {
+                left: tl.0,
+                top: tl.1,
+                right: br.0,
+                bottom: br.1,
+                id: r.id,
+            });
+        }
+
+        // use the secondary series to allow for fine-tuned x values instead of segments
+        chart.draw_secondary_series(
+            data.iter()
+                .enumerate()
+                .map(|(i, r)| {
+                    let s_height = r.score * (c_max / y_max);
+                    let left = i as f32 + if self.show_counts { 0.15 } else { 0.20 };
+                    let right = i as f32 + if self.show_counts { 0.49 } else { 0.80 };
+
+                    Ok(Rectangle::new(
+                        [(left, 0.0), (right, s_height)],
+                        colour(&r.colour)?.filled(),
+                    ))
+                })
+                .collect::<Result<Vec<Rectangle<(f32, f64)>>, ChartError>>()?,
+        )?;
+
+        if self.show_counts {
+            chart.draw_secondary_series(
+                data.iter()
+                    .enumerate()
+                    .map(|(i, r)| {
+                        Ok(Rectangle::new(
+                            [(i as f32 + 0.51, 0.0), (i as f32 + 0.85, r.count as f64)],
+                            colour(&r.colour)?.filled(),
+                        ))
+                    })
+                    .collect::<Result<Vec<Rectangle<(f32, f64)>>, ChartError>>()?,
+            )?;
+        }
+
+        // overlay a box plot and a strip of speaker points on each score bar
+        let mut point_mappings = vec![];
+        let box_style = WHITE.stroke_width((2.0 * scale) as u32);
+        for (i, m) in members.iter().enumerate() {
+            if m.is_empty() {
+                continue;
+            }
+
+            let left = i as f32 + if self.show_counts { 0.15 } else { 0.20 };
+            let right = i as f32 + if self.show_counts { 0.49 } else { 0.80 };
+            let ratio = c_max / y_max;
+            let scores = m.iter().map(|d| d.score).collect::<Vec<f64>>();
+            let q1 = quantile(&scores, 0.25) * ratio;
+            let median = quantile(&scores, 0.5) * ratio;
+            let q3 = quantile(&scores, 0.75) * ratio;
+
+            chart.draw_secondary_series(std::iter::once(Rectangle::new(
+                [(left, q1), (right, q3)],
+                box_style,
+            )))?;
+            chart.draw_secondary_series(std::iter::once(PathElement::new(
+                vec![(left, median), (right, median)],
+                box_style,
+            )))?;
+
+            let points = m
+                .iter()
+                .map(|d| {
+                    let x = left + (right - left) * (0.1 + 0.8 * jitter(d.id));
+                    ((x, d.score * ratio), *d)
+                })
+                .collect::<Vec<((f32, f64), &DispersionResponse)>>();
+
+            chart.draw_secondary_series(
+                points
+                    .iter()
+                    .map(|(p, d)| {
+                        Ok(Circle::new(
+                            *p,
+                            point_size(scale),
+                            colour(&d.colour)?.filled(),
+                        ))
+                    })
+                    .collect::<Result<Vec<Circle<(f32, f64), i32>>, ChartError>>()?,
+            )?;
+            chart.draw_secondary_series(
+                points
+                    .iter()
+                    .map(|(p, _)| Circle::new(*p, point_size(scale), WHITE.stroke_width(1))),
+            )?;
+
+            for (p, d) in points.iter() {
+                let bc = chart.borrow_secondary().backend_coord(p);
+                point_mappings.push(PointMapping {
+                    x: bc.0,
+                    y: bc.1,
+                    id: d.id,
+                    name: d.name.clone(),
+                });
+            }
+        }
+        Ok((bar_mappings, point_mappings))
+    }
+
+    /// Whether we are drawing the per-speaker dispersion overlay.
+
+    fn dispersing(&self) -> bool {
+        *self.breakdown_type != BreakdownType::Speaker && !self.dispersion.is_empty()
+    }
+}
+
+impl Chart for BreakdownChart<'_> {
+    /// Draws the chart on any plotters drawing area, discarding the mappings.
+
+    fn draw_on<DB: DrawingBackend>(
+        &self,
+        drawing_area: &DrawingArea<DB, Shift>,
+        width: f64,
+        scale: f64,
+        caption: Option<&str>,
+    ) -> Result<(), ChartError> {
+        self.draw(drawing_area, width, scale, caption).map(|_| ())
+    }
+}
+
+/// Returns a sane speaker point display size for the given pixel scale.
+
+pub fn point_size(scale: f64) -> i32 {
+    (4.0 * scale) as i32
+}
+
+/// Returns the value at the given quantile of a sorted list of scores.
+
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    sorted[((sorted.len() - 1) as f64 * q).round() as usize]
+}
+
+/// Returns a stable horizontal offset between 0 and 1 for a speaker point, so that
+/// points with similar scores don't sit on top of each other.
+
+fn jitter(id: i32) -> f32 {
+    ((id as u32).wrapping_mul(2654435761) % 1000) as f32 / 1000.0
+}
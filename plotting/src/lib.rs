@@ -0,0 +1,106 @@
+//! The chart drawing logic for the house words app.
+//!
+//! Charts are drawn on any plotters drawing area, so the same code paints the
+//! interactive canvases in the wasm frontend and renders the png/svg images the
+//! backend serves for embeds and link previews.
+
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use std::error::Error;
+use std::fmt;
+
+pub mod breakdown;
+pub mod population;
+
+/// The background colour of the app, used to fill exported and rendered images.
+
+pub const BACKGROUND: RGBColor = RGBColor(0x12, 0x12, 0x12);
+
+/// The height of every chart, in css pixels.
+
+pub const HEIGHT: u32 = 500;
+
+/// An error drawing a chart.
+
+#[derive(Debug)]
+pub struct ChartError;
+
+impl fmt::Display for ChartError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unable to draw chart")
+    }
+}
+
+impl Error for ChartError {}
+
+impl<E: Error + Send + Sync> From<DrawingAreaErrorKind<E>> for ChartError {
+    fn from(_: DrawingAreaErrorKind<E>) -> Self {
+        ChartError
+    }
+}
+
+impl From<hex::FromHexError> for ChartError {
+    fn from(_: hex::FromHexError) -> Self {
+        ChartError
+    }
+}
+
+/// A point coordinate mapping for interactivity.
+///
+/// The struct holds the x and y backend coordinates of a point on a chart, and the
+/// id and name of the speaker or riding represented by that point.
+
+#[derive(Default, Clone)]
+pub struct PointMapping {
+    pub x: i32,
+    pub y: i32,
+    pub id: i32,
+    pub name: String,
+}
+
+/// Describes a chart that can be drawn on any plotters drawing area.
+
+pub trait Chart {
+    /// Draws the chart on a drawing area, at the given reference width (for label
+    /// sizes) and pixel scale, with an optional caption.
+
+    fn draw_on<DB: DrawingBackend>(
+        &self,
+        drawing_area: &DrawingArea<DB, Shift>,
+        width: f64,
+        scale: f64,
+        caption: Option<&str>,
+    ) -> Result<(), ChartError>;
+}
+
+/// Fills a drawing area with the app background, draws a chart on it, and
+/// presents it. This is how standalone images of a chart are made.
+
+pub fn draw_image<DB: DrawingBackend, C: Chart>(
+    drawing_area: &DrawingArea<DB, Shift>,
+    chart: &C,
+    width: f64,
+    scale: f64,
+    caption: Option<&str>,
+) -> Result<(), ChartError> {
+    drawing_area.fill(&BACKGROUND)?;
+    chart.draw_on(drawing_area, width, scale, caption)?;
+    drawing_area.present()?;
+    Ok(())
+}
+
+/// Returns the plotters colour for a hex colour string from the database.
+
+pub(crate) fn colour(hex: &str) -> Result<RGBColor, ChartError> {
+    let rgb = hex::decode(hex)?;
+    if rgb.len() < 3 {
+        return Err(ChartError);
+    }
+    Ok(RGBColor(rgb[0], rgb[1], rgb[2]))
+}
+
+/// Returns the text style for axis labels of the given size.
+
+pub(crate) fn label_style(size: u32) -> TextStyle<'static> {
+    TextStyle::from(("sans-serif", size).into_font()).color(&WHITE)
+}
@@ -0,0 +1,189 @@
+use crate::{colour, label_style, Chart, ChartError, PointMapping};
+use common::models::PopulationResponse;
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use std::cmp::max;
+
+/// A population density data point.
+///
+/// This struct is used once we've made the conversion from the raw backend
+/// response, which only contains the population and area.
+
+struct PopDensity<'a> {
+    id: i32,
+    name: &'a str,
+    pop_density: f64,
+    colour: &'a str,
+    count: i64,
+    score: f32,
+}
+
+/// A population density plot, borrowing the data it draws.
+
+pub struct PopulationChart<'a> {
+    /// The data for this plot.
+    pub data: &'a [PopulationResponse],
+
+    /// Whether we are showing total counts instead of adjusted scores.
+    pub show_counts: bool,
+}
+
+impl PopulationChart<'_> {
+    /// Returns a sane calculated width for the plot, given the window width.
+
+    pub fn width(&self, window_width: f64) -> u32 {
+        ((window_width * 0.97) as u32).clamp(900, 1800)
+    }
+
+    /// Draws the plot on a plotters drawing area and returns the coordinate
+    /// mappings for its points.
+    ///
+    /// The reference width (the graph width, for the canvas) sizes the labels, and
+    /// the scale is the number of real pixels per CSS pixel.
+
+    pub fn draw<DB: DrawingBackend>(
+        &self,
+        drawing_area: &DrawingArea<DB, Shift>,
+        width: f64,
+        scale: f64,
+        caption: Option<&str>,
+    ) -> Result<Vec<PointMapping>, ChartError> {
+        let mut label_size = ((width * scale).sqrt() / 2.5) as u32;
+
+        let data = self
+            .data
+            .iter()
+            .map(|r| PopDensity {
+                id: r.id,
+                name: &r.name,
+                pop_density: r.population as f64 / r.area,
+                colour: &r.colour,
+                score: r.score,
+                count: r.count,
+            })
+            .collect::<Vec<PopDensity>>();
+
+        let x_max = data
+            .iter()
+            .map(|r| r.pop_density)
+            .max_by(|a, b| a.total_cmp(b))
+            .ok_or(ChartError)?;
+        let y_max = data
+            .iter()
+            .map(|r| r.score)
+            .max_by(|a, b| a.total_cmp(b))
+            .ok_or(ChartError)?;
+        let c_max = data.iter().map(|r| r.count).max().ok_or(ChartError)?;
+
+        let bold_line = colour("97948f")?;
+        let light_line = colour("67635c")?;
+
+        label_size = max(
+            (label_size as f64 * (1.0 + (scale * 0.1))) as u32,
+            (8.0 * scale) as u32,
+        );
+        let desc_style = label_style((16.0 * scale) as u32);
+        let mut point_mappings = vec![];
+        let mut builder = ChartBuilder::on(drawing_area);
+        builder
+            .x_label_area_size((50.0 * scale) as u32)
+            .y_label_area_size((70.0 * scale) as u32);
+        if let Some(caption) = caption {
+            builder.caption(caption, ("sans-serif", (30.0 * scale) as u32, &WHITE));
+        }
+
+        if !self.show_counts {
+            let mut chart = builder.build_cartesian_2d((0.02..x_max).log_scale(), 0.0..y_max)?;
+
+            chart
+                .configure_mesh()
+                .x_desc("population per square kilometer")
+                .x_label_style(label_style(label_size))
+                .y_label_style(label_style(label_size))
+                .axis_desc_style(desc_style)
+                .bold_line_style(bold_line)
+                .light_line_style(light_line)
+                .y_desc("word count per 100,000")
+                .draw()?;
+
+            chart.draw_series(
+                data.iter()
+                    .map(|r| {
+                        Ok(Circle::new(
+                            (r.pop_density, r.score),
+                            point_size(scale),
+                            colour(r.colour)?.filled(),
+                        ))
+                    })
+                    .collect::<Result<Vec<Circle<(f64, f32), i32>>, ChartError>>()?,
+            )?;
+
+            for r in data.iter() {
+                let p = chart.backend_coord(&(r.pop_density, r.score));
+                point_mappings.push(PointMapping {
+                    x: p.0,
+                    y: p.1,
+                    id: r.id,
+                    name: r.name.to_owned(),
+                });
+            }
+        } else {
+            let mut chart = builder.build_cartesian_2d((0.02..x_max).log_scale(), 0..c_max)?;
+
+            chart
+                .configure_mesh()
+                .x_desc("population per square kilometer")
+                .x_label_style(label_style(label_size))
+                .y_label_style(label_style(label_size))
+                .axis_desc_style(desc_style)
+                .bold_line_style(bold_line)
+                .light_line_style(light_line)
+                .y_desc("total word count")
+                .y_label_formatter(&|v| format!("{}", *v as u32))
+                .draw()?;
+
+            chart.draw_series(
+                data.iter()
+                    .map(|r| {
+                        Ok(Circle::new(
+                            (r.pop_density, r.count),
+                            point_size(scale),
+                            colour(r.colour)?.filled(),
+                        ))
+                    })
+                    .collect::<Result<Vec<Circle<(f64, i64), i32>>, ChartError>>()?,
+            )?;
+
+            for r in data.iter() {
+                let p = chart.backend_coord(&(r.pop_density, r.count));
+                point_mappings.push(PointMapping {
+                    x: p.0,
+                    y: p.1,
+                    id: r.id,
+                    name: r.name.to_owned(),
+                });
+            }
+        }
+        Ok(point_mappings)
+    }
+}
+
+impl Chart for PopulationChart<'_> {
+    /// Draws the plot on any plotters drawing area, discarding the mappings.
+
+    fn draw_on<DB: DrawingBackend>(
+        &self,
+        drawing_area: &DrawingArea<DB, Shift>,
+        width: f64,
+        scale: f64,
+        caption: Option<&str>,
+    ) -> Result<(), ChartError> {
+        self.draw(drawing_area, width, scale, caption).map(|_| ())
+    }
+}
+
+/// Returns a sane point display size for the given pixel scale.
+
+pub fn point_size(scale: f64) -> i32 {
+    (5.0 * scale) as i32
+}